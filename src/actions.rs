@@ -0,0 +1,237 @@
+//! Views of the list and the actions the TUI and the command line share.
+
+use crate::task::{
+    extract_due, extract_priority, has_tag, new_task_id, parse_tags, set_done, Priority, Task,
+    TaskRef,
+};
+use chrono::{Local, NaiveDate};
+
+/// The folder new tasks go to when none is named.
+pub const DEFAULT_FOLDER: &str = "inbox";
+
+/// The order tasks are listed (and so numbered) in.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    /// The order they were added in.
+    Added,
+    /// High priority first, then medium, low and none; ties keep the order
+    /// they were added in.
+    Priority,
+}
+
+/// What the task table shows. Task numbers are positions in this view, so
+/// everything that resolves a number goes through `folder_indices`.
+/// A tag filter replaces the folder: it shows matching tasks from every folder.
+pub struct View {
+    /// The folder shown when there is no filter.
+    pub folder: String,
+    /// The order rows are listed in.
+    pub sort: SortOrder,
+    /// A tag to show across all folders instead of the folder.
+    pub filter: Option<String>,
+    /// Whether the TUI shows the ID column.
+    pub show_ids: bool,
+    /// The profile whose list is shown; None for the default list.
+    pub profile: Option<String>,
+}
+
+/// A change to the list or the view, as `apply_action` carries it out.
+pub enum CommandAction {
+    /// Adds a task to the view's folder; `due:` and `pri:` tokens in the
+    /// text set its due date and priority.
+    AddTask(String),
+    /// Shows another folder.
+    SwitchFolder(String),
+    /// Deletes a task.
+    DeleteTask(TaskRef),
+    /// Deletes every task in a folder (the view's folder when empty).
+    DeleteFolder(String),
+    /// Marks a task done or not done.
+    SetDone(TaskRef, bool),
+    /// Sets or clears a task's due date.
+    SetDue(TaskRef, Option<NaiveDate>),
+    /// Sets or clears a task's priority.
+    SetPriority(TaskRef, Option<Priority>),
+    /// Switches between sorting by when tasks were added and by priority.
+    ToggleSort,
+    /// Shows or hides the ID column.
+    ToggleIds,
+    /// Sets or clears the tag filter.
+    SetFilter(Option<String>),
+    /// Replaces a task's text, keeping its dates and status.
+    EditTask(TaskRef, String),
+}
+
+/// Indices into `tasks` of the rows shown for `view`, in display order.
+pub fn folder_indices(tasks: &[Task], view: &View) -> Vec<usize> {
+    let mut indices: Vec<usize> = tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| match &view.filter {
+            Some(tag) => has_tag(t, tag),
+            None => t.folder == view.folder,
+        })
+        .map(|(idx, _)| idx)
+        .collect();
+    if view.sort == SortOrder::Priority {
+        // Stable, so tasks of equal priority keep the order they were added in.
+        indices.sort_by_key(|&idx| (tasks[idx].priority.is_none(), tasks[idx].priority));
+    }
+    indices
+}
+
+/// Position in `tasks` of the task a number (within the view) or an ID (in
+/// any folder) refers to.
+pub fn folder_task_index(tasks: &[Task], view: &View, target: &TaskRef) -> Option<usize> {
+    match target {
+        TaskRef::Number(0) => None,
+        TaskRef::Number(number) => folder_indices(tasks, view).get(number - 1).copied(),
+        TaskRef::Id(id) => tasks.iter().position(|t| t.id == *id),
+    }
+}
+
+/// Carries out `action`. Returns false when it had nothing to act on (e.g.
+/// no such task number).
+pub fn apply_action(action: CommandAction, tasks: &mut Vec<Task>, view: &mut View) -> bool {
+    match action {
+        CommandAction::AddTask(text) => {
+            let (text, due) = extract_due(&text, Local::now().date_naive());
+            let (text, priority) = extract_priority(&text);
+            if text.is_empty() {
+                return false;
+            }
+            let tags = parse_tags(&text);
+            tasks.push(Task {
+                id: new_task_id(tasks),
+                text,
+                done: false,
+                folder: view.folder.clone(),
+                created_at: Local::now(),
+                completed_at: None,
+                due,
+                priority,
+                tags,
+            });
+            true
+        }
+        CommandAction::SwitchFolder(name) => {
+            view.folder = name;
+            true
+        }
+        CommandAction::DeleteTask(number) => match folder_task_index(tasks, view, &number) {
+            Some(real_idx) => {
+                tasks.remove(real_idx);
+                true
+            }
+            None => false,
+        },
+        CommandAction::DeleteFolder(name) => {
+            let current_name = if name.is_empty() {
+                view.folder.clone()
+            } else {
+                name
+            };
+            let original_len = tasks.len();
+            tasks.retain(|t| !t.folder.eq_ignore_ascii_case(&current_name));
+            let removed = tasks.len() != original_len;
+            if view.folder.eq_ignore_ascii_case(&current_name) {
+                if let Some(next) = tasks.first() {
+                    view.folder = next.folder.clone();
+                } else {
+                    view.folder = DEFAULT_FOLDER.to_string();
+                }
+            }
+            removed
+        }
+        CommandAction::SetDone(number, done) => match folder_task_index(tasks, view, &number) {
+            Some(real_idx) => {
+                set_done(&mut tasks[real_idx], done);
+                true
+            }
+            None => false,
+        },
+        CommandAction::SetDue(number, due) => match folder_task_index(tasks, view, &number) {
+            Some(real_idx) => {
+                tasks[real_idx].due = due;
+                true
+            }
+            None => false,
+        },
+        CommandAction::SetPriority(number, priority) => {
+            match folder_task_index(tasks, view, &number) {
+                Some(real_idx) => {
+                    tasks[real_idx].priority = priority;
+                    true
+                }
+                None => false,
+            }
+        }
+        CommandAction::ToggleSort => {
+            view.sort = match view.sort {
+                SortOrder::Added => SortOrder::Priority,
+                SortOrder::Priority => SortOrder::Added,
+            };
+            true
+        }
+        CommandAction::ToggleIds => {
+            view.show_ids = !view.show_ids;
+            true
+        }
+        CommandAction::SetFilter(filter) => {
+            view.filter = filter;
+            true
+        }
+        CommandAction::EditTask(number, text) => match folder_task_index(tasks, view, &number) {
+            Some(real_idx) => {
+                // Only the text changes; dates and status stay with the task.
+                // `due:`/`pri:` tokens in the new text still take effect, and
+                // an edit made of nothing else keeps the old text.
+                let task = &mut tasks[real_idx];
+                let (text, due) = extract_due(&text, Local::now().date_naive());
+                let (text, priority) = extract_priority(&text);
+                if !text.is_empty() {
+                    task.tags = parse_tags(&text);
+                    task.text = text;
+                }
+                if due.is_some() {
+                    task.due = due;
+                }
+                if priority.is_some() {
+                    task.priority = priority;
+                }
+                true
+            }
+            None => false,
+        },
+    }
+}
+
+/// The folder the TUI opens on: the first task's, or the default one.
+pub fn first_folder(tasks: &[Task]) -> String {
+    tasks
+        .first()
+        .map(|t| t.folder.clone())
+        .unwrap_or_else(|| DEFAULT_FOLDER.to_string())
+}
+
+/// Folder names in order of first appearance.
+pub fn folder_names(tasks: &[Task]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for task in tasks {
+        if !names.contains(&task.folder) {
+            names.push(task.folder.clone());
+        }
+    }
+    names
+}
+
+/// An ID names a task in any folder, so report it under the folder it is in.
+pub fn target_folder(tasks: &[Task], folder: String, target: &TaskRef) -> String {
+    match target {
+        TaskRef::Id(id) => tasks
+            .iter()
+            .find(|t| t.id == *id)
+            .map_or(folder, |t| t.folder.clone()),
+        TaskRef::Number(_) => folder,
+    }
+}
@@ -0,0 +1,705 @@
+// Running with arguments skips the TUI entirely, so tasks can be managed
+// from scripts, git hooks and cron jobs. Task numbers are folder-relative,
+// exactly as shown in the TUI table.
+use crate::{load_store, open_store};
+use chrono::{Local, NaiveDate};
+use pastel_todo::{
+    apply_action, folder_indices, folder_names, lock_for_write, parse_due_setting,
+    parse_priority_setting, parse_task_ref, priority_name, target_folder, task_text,
+    valid_profile_name, CommandAction, Priority, SortOrder, Storage, Task, TaskQuery, TaskRef,
+    TaskStore, View, DEFAULT_FOLDER,
+};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "\
+Usage: pastel_todo [--file PATH | --profile NAME] [COMMAND] [OPTIONS]
+
+Without a command the interactive TUI is started.
+
+Commands:
+  add [-f FOLDER] TEXT...     Add a task
+  list [-f FOLDER] [--format FMT]
+                              List tasks (all folders if none given)
+  done [-f FOLDER] NUMBER     Mark a task as done
+  undone [-f FOLDER] NUMBER   Mark a task as not done
+  edit [-f FOLDER] NUMBER TEXT...
+                              Replace the text of a task
+  delete [-f FOLDER] NUMBER   Delete a task
+  due [-f FOLDER] NUMBER DATE Set a due date (DATE may be 'none')
+  priority [-f FOLDER] NUMBER LEVEL
+                              Set the priority: high, medium, low or none
+  undo                        Undo the last change (from the TUI or here)
+  redo                        Redo the last undone change
+  folders                     List folders with their task counts
+  help                        Show this message
+
+Task text may contain due:DATE, where DATE is 2026-11-01, today, tomorrow,
+a weekday name such as friday, or an offset such as 3d or 2w, and
+pri:LEVEL, where LEVEL is high, medium or low (or h, m, l).
+
+Words starting with # or + (like #review or +website) become tags.
+
+Options:
+  -f, --folder FOLDER         Folder to act on (default: inbox)
+  -t, --tag TAG               Act on tasks tagged TAG across all folders
+  -s, --sort ORDER            Number tasks by 'added' (default) or 'priority'
+      --format FMT            Output of list: text, json or ndjson (default: text)
+      --file PATH             Task file to use instead of the one in the data
+                              directory (also $PASTEL_TODO_FILE); a .db,
+                              .sqlite or .sqlite3 file is a SQLite database
+      --profile NAME          Use the named profile's list instead of the
+                              default one ('default' picks the default list)";
+
+// Options that pick which list to use rather than what to do with it.
+pub(crate) struct GlobalOptions {
+    pub(crate) file: Option<PathBuf>,
+    pub(crate) profile: Option<String>,
+}
+
+// `--file PATH` and `--profile NAME` (also as `--file=PATH`) work with every
+// command and with the TUI. They go before the command word and are taken out
+// before the rest of the arguments are looked at; anything after the command
+// (or after `--`) is left alone, so task text may contain them.
+pub(crate) fn take_global_options(args: &mut Vec<String>) -> Result<GlobalOptions, String> {
+    let mut file = None;
+    let mut profile = None;
+    while let Some(arg) = args.first().cloned() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (arg.as_str(), None),
+        };
+        if name != "--file" && name != "--profile" {
+            break;
+        }
+        args.remove(0);
+        let value = match inline {
+            Some(value) => value.to_string(),
+            None if !args.is_empty() => args.remove(0),
+            None => return Err(format!("{name} needs a value")),
+        };
+        if name == "--file" {
+            if value.is_empty() {
+                return Err("--file needs a path".to_string());
+            }
+            file = Some(PathBuf::from(value));
+        } else if value == "default" {
+            profile = None;
+        } else if valid_profile_name(&value) {
+            profile = Some(value);
+        } else {
+            return Err(format!(
+                "'{value}' is not a valid profile name (use letters, digits, - and _)"
+            ));
+        }
+    }
+    if file.is_some() && profile.is_some() {
+        return Err("--file and --profile cannot be used together".to_string());
+    }
+    Ok(GlobalOptions { file, profile })
+}
+
+enum CliCommand {
+    Add {
+        folder: String,
+        text: String,
+    },
+    List {
+        folder: Option<String>,
+        format: OutputFormat,
+    },
+    Done {
+        folder: String,
+        number: TaskRef,
+        done: bool,
+    },
+    Delete {
+        folder: String,
+        number: TaskRef,
+    },
+    Due {
+        folder: String,
+        number: TaskRef,
+        due: Option<NaiveDate>,
+    },
+    Priority {
+        folder: String,
+        number: TaskRef,
+        priority: Option<Priority>,
+    },
+    Edit {
+        folder: String,
+        number: TaskRef,
+        text: String,
+    },
+    Undo,
+    Redo,
+    Folders,
+    Help,
+}
+
+enum OutputFormat {
+    Text,
+    Json,
+    Ndjson,
+}
+
+// Options that shape the view task numbers refer to, shared by all commands.
+struct CliView {
+    sort: SortOrder,
+    tag: Option<String>,
+}
+
+fn parse_cli(args: &[String]) -> Result<(CliCommand, CliView), String> {
+    let (name, rest) = args.split_first().ok_or("missing command")?;
+
+    let mut folder: Option<String> = None;
+    let mut format: Option<String> = None;
+    let mut sort = SortOrder::Added;
+    let mut tag: Option<String> = None;
+    let mut positional: Vec<String> = Vec::new();
+    let mut iter = rest.iter();
+    let mut options_done = false;
+    while let Some(arg) = iter.next() {
+        if options_done {
+            positional.push(arg.clone());
+        } else if arg == "--" {
+            options_done = true;
+        } else if arg == "-f" || arg == "--folder" {
+            let value = iter
+                .next()
+                .ok_or_else(|| format!("{arg} needs a folder name"))?;
+            folder = Some(value.clone());
+        } else if let Some(value) = arg.strip_prefix("--folder=") {
+            folder = Some(value.to_string());
+        } else if arg == "--format" {
+            let value = iter.next().ok_or("--format needs a value")?;
+            format = Some(value.clone());
+        } else if let Some(value) = arg.strip_prefix("--format=") {
+            format = Some(value.to_string());
+        } else if arg == "-s" || arg == "--sort" || arg.starts_with("--sort=") {
+            let value = match arg.strip_prefix("--sort=") {
+                Some(value) => value,
+                None => iter
+                    .next()
+                    .ok_or_else(|| format!("{arg} needs 'added' or 'priority'"))?,
+            };
+            sort = match value {
+                "added" => SortOrder::Added,
+                "priority" => SortOrder::Priority,
+                other => return Err(format!("unknown sort order '{other}'")),
+            };
+        } else if arg == "-t" || arg == "--tag" {
+            let value = iter.next().ok_or_else(|| format!("{arg} needs a tag"))?;
+            tag = Some(value.clone());
+        } else if let Some(value) = arg.strip_prefix("--tag=") {
+            tag = Some(value.to_string());
+        } else if ["--file", "--profile"].contains(&arg.split('=').next().unwrap_or_default()) {
+            return Err(format!("{arg} goes before the command"));
+        } else if arg.starts_with('-') && arg.len() > 1 {
+            return Err(format!("unknown option '{arg}'"));
+        } else {
+            positional.push(arg.clone());
+        }
+    }
+
+    if let Some(name) = &folder {
+        if name.trim().is_empty() {
+            return Err("folder name cannot be empty".to_string());
+        }
+    }
+    if format.is_some() && !matches!(name.as_str(), "list" | "ls") {
+        return Err("--format is only supported by list".to_string());
+    }
+    let folder_or_default = || folder.clone().unwrap_or_else(|| DEFAULT_FOLDER.to_string());
+    let number = |positional: &[String]| -> Result<TaskRef, String> {
+        match positional {
+            [n] => parse_task_ref(n).ok_or_else(|| format!("'{n}' is not a task number or ID")),
+            [] => Err(format!("{name} needs a task number")),
+            _ => Err(format!("{name} takes a single task number")),
+        }
+    };
+
+    let command = match name.as_str() {
+        "add" => {
+            let text = positional.join(" ").trim().to_string();
+            if text.is_empty() {
+                return Err("add needs the task text".to_string());
+            }
+            if task_text(&text, Local::now().date_naive()).is_empty() {
+                return Err("add needs the task text, not only due: and pri:".to_string());
+            }
+            CliCommand::Add {
+                folder: folder_or_default(),
+                text,
+            }
+        }
+        "list" | "ls" => {
+            if !positional.is_empty() {
+                return Err("list takes no arguments".to_string());
+            }
+            let format = match format.as_deref() {
+                None | Some("text") => OutputFormat::Text,
+                Some("json") => OutputFormat::Json,
+                Some("ndjson") | Some("jsonl") => OutputFormat::Ndjson,
+                Some(other) => return Err(format!("unknown format '{other}'")),
+            };
+            CliCommand::List { folder, format }
+        }
+        "done" | "undone" => CliCommand::Done {
+            number: number(&positional)?,
+            folder: folder_or_default(),
+            done: name == "done",
+        },
+        "delete" | "rm" => CliCommand::Delete {
+            number: number(&positional)?,
+            folder: folder_or_default(),
+        },
+        "due" => {
+            let (first, rest) = positional
+                .split_first()
+                .ok_or("due needs a task number and a date")?;
+            let due = parse_due_setting(&rest.join(" "))
+                .ok_or_else(|| format!("cannot understand due date '{}'", rest.join(" ")))?;
+            CliCommand::Due {
+                number: number(std::slice::from_ref(first))?,
+                folder: folder_or_default(),
+                due,
+            }
+        }
+        "priority" | "pri" => {
+            let (first, rest) = positional
+                .split_first()
+                .ok_or("priority needs a task number and a level")?;
+            let priority = parse_priority_setting(&rest.join(" "))
+                .ok_or_else(|| format!("unknown priority '{}'", rest.join(" ")))?;
+            CliCommand::Priority {
+                number: number(std::slice::from_ref(first))?,
+                folder: folder_or_default(),
+                priority,
+            }
+        }
+        "edit" => {
+            let (first, rest) = positional
+                .split_first()
+                .ok_or("edit needs a task number and the new text")?;
+            let text = rest.join(" ").trim().to_string();
+            if text.is_empty() {
+                return Err("edit needs the new task text".to_string());
+            }
+            CliCommand::Edit {
+                number: number(std::slice::from_ref(first))?,
+                folder: folder_or_default(),
+                text,
+            }
+        }
+        "undo" => CliCommand::Undo,
+        "redo" => CliCommand::Redo,
+        "folders" => CliCommand::Folders,
+        "help" | "-h" | "--help" => CliCommand::Help,
+        other => return Err(format!("unknown command '{other}'")),
+    };
+    Ok((command, CliView { sort, tag }))
+}
+
+pub(crate) fn run_cli(args: &[String], options: GlobalOptions) -> i32 {
+    let (command, CliView { sort, tag }) = match parse_cli(args) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("pastel_todo: {err}");
+            eprintln!("Try 'pastel_todo help' for usage.");
+            return 2;
+        }
+    };
+
+    // Help needs no task list, so it works even where the list cannot be opened.
+    if let CliCommand::Help = command {
+        return output_status(writeln!(io::stdout().lock(), "{USAGE}"));
+    }
+
+    let mut store = open_store(options.file.clone(), options.profile.as_deref());
+    // Commands hold the write lock from loading to saving. Listing takes it
+    // too: loading can rewrite the file (an upgrade, quarantined lines, new
+    // IDs).
+    let _lock = match lock_for_write(store.path()) {
+        Ok(lock) => lock,
+        Err(err) => {
+            eprintln!("pastel_todo: cannot lock {}: {err}", store.path().display());
+            return 1;
+        }
+    };
+    // Listing only reads, so the store is asked for just the tasks it shows.
+    let mut tasks = match &command {
+        CliCommand::List { folder, .. } => {
+            let query = TaskQuery {
+                folder: if tag.is_some() { None } else { folder.clone() },
+                tag: tag.clone(),
+            };
+            store.query(&query).unwrap_or_else(|err| {
+                eprintln!("pastel_todo: cannot load tasks: {err}");
+                process::exit(1);
+            })
+        }
+        _ => load_store(store.as_mut()),
+    };
+    print_warnings(store.as_mut());
+    let mut storage = Storage::new(store, &tasks, options.file);
+    let before = tasks.clone();
+    let view_of = |folder: String| View {
+        folder,
+        sort,
+        filter: tag.clone(),
+        show_ids: false,
+        profile: options.profile.clone(),
+    };
+    let mut out = io::stdout().lock();
+    let written = match command {
+        CliCommand::Add { folder, text } => {
+            let mut view = view_of(folder);
+            apply_action(CommandAction::AddTask(text), &mut tasks, &mut view);
+            let added = tasks.len() - 1;
+            let number = folder_indices(&tasks, &view)
+                .iter()
+                .position(|&idx| idx == added)
+                .map_or(0, |pos| pos + 1);
+            writeln!(
+                out,
+                "Added task {number} (id {}) to {}.",
+                tasks[added].id, view.folder
+            )
+        }
+        CliCommand::List { folder, format } => {
+            let views: Vec<View> = match (folder, &tag) {
+                (_, Some(_)) => vec![view_of(String::new())],
+                (Some(name), None) => vec![view_of(name)],
+                (None, None) => folder_names(&tasks).into_iter().map(view_of).collect(),
+            };
+            match format {
+                OutputFormat::Text => print_task_list(&mut out, &tasks, &views),
+                OutputFormat::Json => {
+                    let objects = folder_listing(&tasks, &views)
+                        .into_iter()
+                        .map(|(number, task)| format!("  {}", task_json(task, number)))
+                        .collect::<Vec<_>>();
+                    if objects.is_empty() {
+                        writeln!(out, "[]")
+                    } else {
+                        writeln!(out, "[\n{}\n]", objects.join(",\n"))
+                    }
+                }
+                OutputFormat::Ndjson => folder_listing(&tasks, &views)
+                    .into_iter()
+                    .try_for_each(|(number, task)| writeln!(out, "{}", task_json(task, number))),
+            }
+        }
+        CliCommand::Done {
+            folder,
+            number,
+            done,
+        } => {
+            let mut view = view_of(target_folder(&tasks, folder, &number));
+            if !apply_action(
+                CommandAction::SetDone(number.clone(), done),
+                &mut tasks,
+                &mut view,
+            ) {
+                eprintln!("pastel_todo: no task {number} in {}", view.folder);
+                return 1;
+            }
+            let state = if done { "done" } else { "not done" };
+            writeln!(out, "Marked task {number} in {} as {state}.", view.folder)
+        }
+        CliCommand::Delete { folder, number } => {
+            let mut view = view_of(target_folder(&tasks, folder, &number));
+            if !apply_action(
+                CommandAction::DeleteTask(number.clone()),
+                &mut tasks,
+                &mut view,
+            ) {
+                eprintln!("pastel_todo: no task {number} in {}", view.folder);
+                return 1;
+            }
+            writeln!(out, "Deleted task {number} from {}.", view.folder)
+        }
+        CliCommand::Due {
+            folder,
+            number,
+            due,
+        } => {
+            let mut view = view_of(target_folder(&tasks, folder, &number));
+            if !apply_action(
+                CommandAction::SetDue(number.clone(), due),
+                &mut tasks,
+                &mut view,
+            ) {
+                eprintln!("pastel_todo: no task {number} in {}", view.folder);
+                return 1;
+            }
+            match due {
+                Some(date) => writeln!(
+                    out,
+                    "Task {number} in {} is due {}.",
+                    view.folder,
+                    date.format("%d/%m/%y")
+                ),
+                None => writeln!(
+                    out,
+                    "Cleared the due date of task {number} in {}.",
+                    view.folder
+                ),
+            }
+        }
+        CliCommand::Priority {
+            folder,
+            number,
+            priority,
+        } => {
+            let mut view = view_of(target_folder(&tasks, folder, &number));
+            let action = CommandAction::SetPriority(number.clone(), priority);
+            if !apply_action(action, &mut tasks, &mut view) {
+                eprintln!("pastel_todo: no task {number} in {}", view.folder);
+                return 1;
+            }
+            match priority {
+                Some(level) => writeln!(
+                    out,
+                    "Task {number} in {} now has {} priority.",
+                    view.folder,
+                    priority_name(level)
+                ),
+                None => writeln!(
+                    out,
+                    "Cleared the priority of task {number} in {}.",
+                    view.folder
+                ),
+            }
+        }
+        CliCommand::Edit {
+            folder,
+            number,
+            text,
+        } => {
+            let mut view = view_of(target_folder(&tasks, folder, &number));
+            if !apply_action(
+                CommandAction::EditTask(number.clone(), text),
+                &mut tasks,
+                &mut view,
+            ) {
+                eprintln!("pastel_todo: no task {number} in {}", view.folder);
+                return 1;
+            }
+            writeln!(out, "Updated task {number} in {}.", view.folder)
+        }
+        CliCommand::Undo | CliCommand::Redo => {
+            let undo = matches!(command, CliCommand::Undo);
+            let changed = if undo {
+                storage.history.undo(&mut tasks)
+            } else {
+                storage.history.redo(&mut tasks)
+            };
+            if let Some(err) = storage.save(&before, &tasks, false) {
+                eprintln!("pastel_todo: {err}");
+                return 1;
+            }
+            print_warnings(storage.store.as_mut());
+            if !changed {
+                eprintln!(
+                    "pastel_todo: nothing to {}",
+                    if undo { "undo" } else { "redo" }
+                );
+                return 1;
+            }
+            let done = if undo {
+                "Undid the last change"
+            } else {
+                "Redid the last undone change"
+            };
+            return output_status(writeln!(out, "{done}."));
+        }
+        CliCommand::Folders => folder_names(&tasks).into_iter().try_for_each(|name| {
+            let count = tasks.iter().filter(|t| t.folder == name).count();
+            writeln!(out, "{name}\t{count}")
+        }),
+        CliCommand::Help => unreachable!("help is answered before the store opens"),
+    };
+    if tasks != before {
+        storage.history.record(&before, &tasks);
+        if let Some(err) = storage.save(&before, &tasks, false) {
+            eprintln!("pastel_todo: {err}");
+            return 1;
+        }
+        print_warnings(storage.store.as_mut());
+    }
+    output_status(written)
+}
+
+// Loading and saving can leave warnings (quarantined lines, a failed
+// backup); they go to stderr so the output stays clean.
+fn print_warnings(store: &mut dyn TaskStore) {
+    for warning in store.take_warnings() {
+        eprintln!("pastel_todo: {}", warning.trim_start_matches([' ', '⚠']));
+    }
+}
+
+// The exit code once the output is written. A reader that stops early, as
+// `| head` does, closes the pipe; that is not an error worth reporting.
+fn output_status(written: io::Result<()>) -> i32 {
+    match written {
+        Ok(()) => 0,
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => 0,
+        Err(err) => {
+            eprintln!("pastel_todo: cannot write output: {err}");
+            1
+        }
+    }
+}
+
+fn print_task_list(out: &mut impl Write, tasks: &[Task], views: &[View]) -> io::Result<()> {
+    for (i, view) in views.iter().enumerate() {
+        if views.len() > 1 {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            writeln!(out, "{}:", view.folder)?;
+        }
+        for (order, idx) in folder_indices(tasks, view).into_iter().enumerate() {
+            let task = &tasks[idx];
+            let folder = if view.filter.is_some() {
+                format!("{}: ", task.folder)
+            } else {
+                String::new()
+            };
+            let due = task
+                .due
+                .map(|d| format!("  due {}", d.format("%d/%m/%y")))
+                .unwrap_or_default();
+            let completed = task
+                .completed_at
+                .map(|d| format!("  completed {}", d.format("%d/%m/%y")))
+                .unwrap_or_default();
+            let priority = task
+                .priority
+                .map(|p| format!("  {} priority", priority_name(p)))
+                .unwrap_or_default();
+            writeln!(
+                out,
+                "{:>3}. [{}] {}{}  {}{}{}{}",
+                order + 1,
+                if task.done { "x" } else { " " },
+                folder,
+                task.text,
+                task.created_at.format("%d/%m/%y"),
+                completed,
+                due,
+                priority
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// Tasks of the given views, one view after the other, paired with their
+// number within the view.
+fn folder_listing<'a>(tasks: &'a [Task], views: &[View]) -> Vec<(usize, &'a Task)> {
+    views
+        .iter()
+        .flat_map(|view| {
+            folder_indices(tasks, view)
+                .into_iter()
+                .enumerate()
+                .map(|(order, idx)| (order + 1, &tasks[idx]))
+        })
+        .collect()
+}
+
+fn task_json(task: &Task, number: usize) -> String {
+    format!(
+        "{{\"id\":{},\"number\":{},\"folder\":{},\"text\":{},\"done\":{},\"created_at\":{},\"completed_at\":{},\"due\":{},\"priority\":{},\"tags\":[{}]}}",
+        json_string(&task.id),
+        number,
+        json_string(&task.folder),
+        json_string(&task.text),
+        task.done,
+        json_string(&task.created_at.to_rfc3339()),
+        task.completed_at
+            .map(|d| json_string(&d.to_rfc3339()))
+            .unwrap_or_else(|| "null".to_string()),
+        task.due
+            .map(|d| json_string(&d.format("%Y-%m-%d").to_string()))
+            .unwrap_or_else(|| "null".to_string()),
+        task.priority
+            .map(|p| json_string(priority_name(p)))
+            .unwrap_or_else(|| "null".to_string()),
+        task.tags
+            .iter()
+            .map(|t| json_string(t))
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\u{7f}' => {
+                out.push_str(&format!("\\u{:04x}", c as u32));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A reader that has gone away, like `head` after its first lines.
+    struct ClosedPipe;
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn a_closed_pipe_ends_the_listing_quietly() {
+        let mut tasks = Vec::new();
+        let mut view = View {
+            folder: DEFAULT_FOLDER.to_string(),
+            sort: SortOrder::Added,
+            filter: None,
+            show_ids: false,
+            profile: None,
+        };
+        apply_action(
+            CommandAction::AddTask("a".to_string()),
+            &mut tasks,
+            &mut view,
+        );
+        let written = print_task_list(&mut ClosedPipe, &tasks, &[view]);
+        assert_eq!(
+            written.as_ref().map_err(io::Error::kind),
+            Err(io::ErrorKind::BrokenPipe)
+        );
+        assert_eq!(output_status(written), 0);
+        assert_eq!(
+            output_status(Err(io::ErrorKind::PermissionDenied.into())),
+            1
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Both files start with a header naming the format version, e.g.
+//! "# pastel_todo tasks v2". From v2 on every column is escaped, so tabs,
+//! newlines and backslashes in folders or text cannot split a record. Files
+//! without a header are v1: raw columns, text being everything after the
+//! fields.
+
+use crate::task::{parse_priority, parse_tags, priority_name, Task};
+use chrono::{DateTime, Local, NaiveDate};
+
+pub(crate) const TASKS_HEADER: &str = "# pastel_todo tasks v";
+
+pub(crate) const HISTORY_HEADER: &str = "# pastel_todo history v";
+
+pub(crate) const FORMAT_VERSION: u32 = 2;
+
+pub(crate) fn format_version(line: &str, header: &str) -> Option<u32> {
+    line.strip_prefix(header)?.trim().parse().ok()
+}
+
+pub(crate) fn escape_field(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+pub(crate) fn unescape_field(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Line format: done flag, folder and creation time, then optional
+/// `key:value` fields, then the task text (always last). Files written
+/// before a field existed simply lack that column.
+pub(crate) fn format_task_line(t: &Task) -> String {
+    let flag = if t.done { "1" } else { "0" };
+    let mut fields = String::new();
+    if !t.id.is_empty() {
+        fields.push_str(&format!("id:{}\t", escape_field(&t.id)));
+    }
+    if let Some(completed_at) = t.completed_at {
+        fields.push_str(&format!("completed:{}\t", completed_at.to_rfc3339()));
+    }
+    if let Some(due) = t.due {
+        fields.push_str(&format!("due:{}\t", due.format("%Y-%m-%d")));
+    }
+    if let Some(priority) = t.priority {
+        fields.push_str(&format!("pri:{}\t", priority_name(priority)));
+    }
+    if !t.tags.is_empty() {
+        fields.push_str(&format!("tags:{}\t", escape_field(&t.tags.join(","))));
+    }
+    format!(
+        "{}\t{}\t{}\t{}{}",
+        flag,
+        escape_field(&t.folder),
+        t.created_at.to_rfc3339(),
+        fields,
+        escape_field(&t.text)
+    )
+}
+
+pub(crate) fn parse_task_line(l: &str, version: u32) -> Result<Task, String> {
+    let p: Vec<String> = if version >= 2 {
+        l.split('\t').map(unescape_field).collect()
+    } else {
+        l.split('\t').map(str::to_string).collect()
+    };
+    if p.len() < 4 {
+        return Err(format!("expected at least 4 columns, found {}", p.len()));
+    }
+    let done = match p[0].as_str() {
+        "1" => true,
+        "0" => false,
+        other => return Err(format!("bad done flag '{other}'")),
+    };
+    let folder = p[1].clone();
+    let created_at = DateTime::parse_from_rfc3339(&p[2])
+        .map_err(|_| format!("bad creation time '{}'", p[2]))?
+        .with_timezone(&Local);
+    let mut id = String::new();
+    let mut completed_at = None;
+    let mut due = None;
+    let mut priority = None;
+    let mut tags = None;
+    let mut rest = &p[3..];
+    while rest.len() > 1 {
+        let Some((key, value)) = rest[0].split_once(':') else {
+            if version >= 2 {
+                rest = &rest[1..];
+                continue;
+            }
+            break;
+        };
+        match key {
+            "id" => id = value.to_string(),
+            "completed" => {
+                let time = DateTime::parse_from_rfc3339(value)
+                    .map_err(|_| format!("bad completion time '{value}'"))?;
+                completed_at = Some(time.with_timezone(&Local));
+            }
+            "due" => {
+                let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                    .map_err(|_| format!("bad due date '{value}'"))?;
+                due = Some(date);
+            }
+            "pri" => {
+                priority =
+                    Some(parse_priority(value).ok_or(format!("unknown priority '{value}'"))?);
+            }
+            "tags" => tags = Some(value.split(',').map(str::to_string).collect()),
+            // v2 text is always one column, so unknown fields can be skipped.
+            _ if version >= 2 => {}
+            _ => break,
+        }
+        rest = &rest[1..];
+    }
+    let text = rest.join("\t");
+    // Files from before tags were stored still have them in the text.
+    let tags = tags.unwrap_or_else(|| parse_tags(&text));
+    Ok(Task {
+        id,
+        text,
+        done,
+        folder,
+        created_at,
+        completed_at,
+        due,
+        priority,
+        tags,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::tests::task;
+    use crate::task::Priority;
+
+    #[test]
+    fn escaping_round_trips() {
+        for value in [
+            "plain",
+            "tab\there",
+            "two\nlines",
+            "back\\slash",
+            "\\t",
+            "end\\",
+            "",
+        ] {
+            let escaped = escape_field(value);
+            assert!(!escaped.contains(['\t', '\n']), "{escaped:?}");
+            assert_eq!(unescape_field(&escaped), value);
+        }
+    }
+
+    #[test]
+    fn folders_and_text_with_separators_survive_a_save() {
+        let mut t = task("abcdef", "line one\nline two\twith a tab and a \\");
+        t.folder = "work\tlater\\now\n".to_string();
+        t.due = NaiveDate::from_ymd_opt(2026, 11, 1);
+        t.priority = Some(Priority::High);
+        t.tags = vec!["#review".to_string()];
+        let line = format_task_line(&t);
+        assert!(!line.contains('\n'));
+        assert_eq!(line.split('\t').count(), 8);
+        assert_eq!(parse_task_line(&line, FORMAT_VERSION), Ok(t));
+    }
+
+    #[test]
+    fn reads_a_line_written_before_the_format_had_a_version() {
+        // done flag, folder, creation time and text, as the first release
+        // wrote them: unescaped, and a tab in the text split it.
+        let line = "1\twork\t2026-10-17T12:15:58+02:00\tReview PR #42: see\tnotes";
+        let t = parse_task_line(line, 1).unwrap();
+        assert!(t.done);
+        assert_eq!(t.folder, "work");
+        let created = DateTime::parse_from_rfc3339("2026-10-17T12:15:58+02:00").unwrap();
+        assert_eq!(t.created_at, created);
+        assert_eq!(t.text, "Review PR #42: see\tnotes");
+        assert_eq!(t.tags, ["#42"]);
+        assert_eq!(t.id, "");
+        assert_eq!(t.completed_at, None);
+    }
+
+    #[test]
+    fn rejects_lines_it_cannot_read() {
+        assert!(parse_task_line("0\tinbox\tnot a time\ttext", 2).is_err());
+        assert!(parse_task_line("x\tinbox\t2026-10-17T12:00:00+00:00\ttext", 2).is_err());
+        assert!(parse_task_line("0\tinbox", 2).is_err());
+    }
+}
@@ -0,0 +1,349 @@
+//! Every key press or command that changes the task list is recorded as the
+//! list of changes between the task list before and after it. Undoing plays
+//! those changes backwards. Both stacks are saved by the task store
+//! (tasks.history.tsv, or a table in tasks.db) so they survive restarts, including
+//! changes made from the command line.
+
+use crate::format::{format_task_line, parse_task_line, FORMAT_VERSION};
+use crate::task::Task;
+
+/// Most undo steps kept; older ones are dropped.
+pub const HISTORY_LIMIT: usize = 50;
+
+/// One step from one list to the next, as produced by `diff_tasks`.
+#[derive(Clone)]
+pub enum Change {
+    /// A task was added.
+    Insert {
+        /// Where it went.
+        index: usize,
+        /// The new task.
+        task: Task,
+    },
+    /// A task was deleted.
+    Remove {
+        /// Where it was.
+        index: usize,
+        /// The deleted task.
+        task: Task,
+    },
+    /// A task was changed in place.
+    Update {
+        /// Where it is.
+        index: usize,
+        /// The task as it was.
+        before: Task,
+        /// The task as it is now.
+        after: Task,
+    },
+}
+
+/// The undo and redo stacks. Each entry is the changes one action made.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Vec<Change>>,
+    redo: Vec<Vec<Change>>,
+}
+
+impl History {
+    /// Pushes the changes from `before` to `after` as one undo step and
+    /// forgets what could be redone. Does nothing if the lists are equal.
+    pub fn record(&mut self, before: &[Task], after: &[Task]) {
+        let changes = diff_tasks(before, after);
+        if changes.is_empty() {
+            return;
+        }
+        self.undo.push(changes);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Takes back the last step. False when there is none or it no longer
+    /// matches `tasks`, in which case the whole history is dropped.
+    pub fn undo(&mut self, tasks: &mut Vec<Task>) -> bool {
+        let Some(changes) = self.undo.pop() else {
+            return false;
+        };
+        if apply_changes(tasks, &changes, false) {
+            self.redo.push(changes);
+            true
+        } else {
+            // The list no longer matches what was recorded (e.g. the file
+            // was edited by hand), so the rest of the history is stale too.
+            self.undo.clear();
+            self.redo.clear();
+            false
+        }
+    }
+
+    /// Repeats the last undone step; fails like `undo`.
+    pub fn redo(&mut self, tasks: &mut Vec<Task>) -> bool {
+        let Some(changes) = self.redo.pop() else {
+            return false;
+        };
+        if apply_changes(tasks, &changes, true) {
+            self.undo.push(changes);
+            true
+        } else {
+            self.undo.clear();
+            self.redo.clear();
+            false
+        }
+    }
+}
+
+/// Walks both lists in step. Indices are positions at the moment each change
+/// is applied, so the changes replay in order (and reverse in reverse order).
+/// Every action inserts, removes or updates tasks without reordering the
+/// rest, which is all this needs to handle.
+pub fn diff_tasks(before: &[Task], after: &[Task]) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() || j < after.len() {
+        let removed_left = before.len() - i;
+        let inserted_left = after.len() - j;
+        if i < before.len() && j < after.len() && before[i] == after[j] {
+            i += 1;
+            j += 1;
+        } else if removed_left > inserted_left {
+            changes.push(Change::Remove {
+                index: j,
+                task: before[i].clone(),
+            });
+            i += 1;
+        } else if inserted_left > removed_left {
+            changes.push(Change::Insert {
+                index: j,
+                task: after[j].clone(),
+            });
+            j += 1;
+        } else {
+            changes.push(Change::Update {
+                index: j,
+                before: before[i].clone(),
+                after: after[j].clone(),
+            });
+            i += 1;
+            j += 1;
+        }
+    }
+    changes
+}
+
+/// Applies the changes to a copy first and only commits if every change
+/// still matches the list, so a failed undo leaves the tasks untouched.
+pub fn apply_changes(tasks: &mut Vec<Task>, changes: &[Change], forward: bool) -> bool {
+    let mut next = tasks.clone();
+    let ordered: Vec<&Change> = if forward {
+        changes.iter().collect()
+    } else {
+        changes.iter().rev().collect()
+    };
+    for change in ordered {
+        let ok = match (change, forward) {
+            (Change::Insert { index, task }, true) | (Change::Remove { index, task }, false) => {
+                if *index > next.len() {
+                    false
+                } else {
+                    next.insert(*index, task.clone());
+                    true
+                }
+            }
+            (Change::Remove { index, task }, true) | (Change::Insert { index, task }, false) => {
+                if next.get(*index) == Some(task) {
+                    next.remove(*index);
+                    true
+                } else {
+                    false
+                }
+            }
+            (
+                Change::Update {
+                    index,
+                    before,
+                    after,
+                },
+                _,
+            ) => {
+                let (from, to) = if forward {
+                    (before, after)
+                } else {
+                    (after, before)
+                };
+                if next.get(*index) == Some(from) {
+                    next[*index] = to.clone();
+                    true
+                } else {
+                    false
+                }
+            }
+        };
+        if !ok {
+            return false;
+        }
+    }
+    *tasks = next;
+    true
+}
+
+/// One line per change: stack (u/r), entry number, kind, index, task line.
+/// An update is stored as a "before" line followed by an "after" line.
+pub(crate) fn format_history(history: &History) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (stack, entries) in [("u", &history.undo), ("r", &history.redo)] {
+        for (n, changes) in entries.iter().enumerate() {
+            for change in changes {
+                match change {
+                    Change::Insert { index, task } => lines.push(format!(
+                        "{stack}\t{n}\tinsert\t{index}\t{}",
+                        format_task_line(task)
+                    )),
+                    Change::Remove { index, task } => lines.push(format!(
+                        "{stack}\t{n}\tremove\t{index}\t{}",
+                        format_task_line(task)
+                    )),
+                    Change::Update {
+                        index,
+                        before,
+                        after,
+                    } => {
+                        lines.push(format!(
+                            "{stack}\t{n}\tbefore\t{index}\t{}",
+                            format_task_line(before)
+                        ));
+                        lines.push(format!(
+                            "{stack}\t{n}\tafter\t{index}\t{}",
+                            format_task_line(after)
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// Lines that cannot be read back drop the whole history rather than trust it.
+pub(crate) fn parse_history<'a>(lines: impl IntoIterator<Item = &'a str>, version: u32) -> History {
+    if version > FORMAT_VERSION {
+        return History::default();
+    }
+    let mut history = History::default();
+    let mut pending_before: Option<Task> = None;
+    let mut last_entry: Option<(String, String)> = None;
+    for line in lines {
+        let p: Vec<&str> = line.splitn(5, '\t').collect();
+        let (Some(task), [stack, n, kind, index, _]) = (
+            p.get(4)
+                .and_then(|rest| parse_task_line(rest, version).ok()),
+            p.as_slice(),
+        ) else {
+            return History::default();
+        };
+        let Ok(index) = index.parse::<usize>() else {
+            return History::default();
+        };
+        let entries = match *stack {
+            "u" => &mut history.undo,
+            "r" => &mut history.redo,
+            _ => return History::default(),
+        };
+        let entry = (stack.to_string(), n.to_string());
+        if last_entry.as_ref() != Some(&entry) {
+            entries.push(Vec::new());
+            last_entry = Some(entry);
+        }
+        let changes = entries.last_mut().expect("entry pushed above");
+        match *kind {
+            "insert" => changes.push(Change::Insert { index, task }),
+            "remove" => changes.push(Change::Remove { index, task }),
+            "before" => pending_before = Some(task),
+            "after" => match pending_before.take() {
+                Some(before) => changes.push(Change::Update {
+                    index,
+                    before,
+                    after: task,
+                }),
+                None => return History::default(),
+            },
+            _ => return History::default(),
+        }
+    }
+    history
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::tests::task;
+
+    #[test]
+    fn changes_undo_and_redo() {
+        let before = vec![task("a", "one"), task("b", "two"), task("c", "three")];
+        let cases = [
+            vec![
+                task("a", "one"),
+                task("n", "new"),
+                task("b", "two"),
+                task("c", "three"),
+            ],
+            vec![task("a", "one"), task("c", "three")],
+            vec![
+                task("a", "one"),
+                task("b", "two, edited"),
+                task("c", "three"),
+            ],
+            vec![task("b", "two, edited")],
+            Vec::new(),
+        ];
+        for after in cases {
+            let changes = diff_tasks(&before, &after);
+            let mut tasks = after.clone();
+            assert!(apply_changes(&mut tasks, &changes, false));
+            assert_eq!(tasks, before);
+            assert!(apply_changes(&mut tasks, &changes, true));
+            assert_eq!(tasks, after);
+        }
+    }
+
+    #[test]
+    fn equal_lists_have_no_changes() {
+        let tasks = vec![task("a", "one"), task("b", "two")];
+        assert!(diff_tasks(&tasks, &tasks).is_empty());
+    }
+
+    #[test]
+    fn undo_that_no_longer_fits_changes_nothing() {
+        let before = vec![task("a", "one"), task("b", "two")];
+        let after = vec![task("a", "one, edited"), task("b", "two, edited")];
+        let changes = diff_tasks(&before, &after);
+        // b was changed again since, so undoing the first edit alone would
+        // leave half of the step applied.
+        let mut tasks = vec![task("a", "one, edited"), task("b", "two, edited again")];
+        let unchanged = tasks.clone();
+        assert!(!apply_changes(&mut tasks, &changes, false));
+        assert_eq!(tasks, unchanged);
+    }
+
+    #[test]
+    fn history_survives_a_save() {
+        let mut history = History::default();
+        let first = vec![task("a", "one")];
+        let second = vec![task("a", "one\twith a tab"), task("b", "two")];
+        history.record(&[], &first);
+        history.record(&first, &second);
+        let mut tasks = second.clone();
+        assert!(history.undo(&mut tasks));
+
+        let lines = format_history(&history);
+        let mut restored = parse_history(lines.iter().map(String::as_str), FORMAT_VERSION);
+        assert!(restored.redo(&mut tasks));
+        assert_eq!(tasks, second);
+        assert!(restored.undo(&mut tasks));
+        assert!(restored.undo(&mut tasks));
+        assert!(tasks.is_empty());
+        assert!(!restored.undo(&mut tasks));
+    }
+}
@@ -0,0 +1,59 @@
+//! The task lists behind pastel_todo: the task model, the stores that keep
+//! lists on disk (TSV or SQLite), locking, and the actions the TUI and the
+//! command line apply to a list. The TUI itself, minus the terminal, is in
+//! [`tui`].
+//!
+//! A script that adds a task the way `pastel_todo add` does:
+//!
+//! ```no_run
+//! use pastel_todo::{
+//!     apply_action, lock_for_write, try_open_store, CommandAction, SortOrder, Storage, View,
+//! };
+//!
+//! let mut store = try_open_store(None, None)?;
+//! let _lock = lock_for_write(store.path()).map_err(|err| err.to_string())?;
+//! let mut tasks = store.load().map_err(|err| err.to_string())?;
+//! let mut storage = Storage::new(store, &tasks, None);
+//! let mut view = View {
+//!     folder: "inbox".to_string(),
+//!     sort: SortOrder::Added,
+//!     filter: None,
+//!     show_ids: false,
+//!     profile: None,
+//! };
+//! let before = tasks.clone();
+//! apply_action(CommandAction::AddTask("water the plants".into()), &mut tasks, &mut view);
+//! storage.history.record(&before, &tasks);
+//! if let Some(err) = storage.save(&before, &tasks, false) {
+//!     return Err(err);
+//! }
+//! # Ok::<(), String>(())
+//! ```
+
+#![warn(missing_docs)]
+
+mod actions;
+mod format;
+mod history;
+mod location;
+mod lock;
+mod store;
+mod task;
+pub mod tui;
+
+pub use actions::{
+    apply_action, first_folder, folder_indices, folder_names, folder_task_index, target_folder,
+    CommandAction, SortOrder, View, DEFAULT_FOLDER,
+};
+pub use history::{apply_changes, diff_tasks, Change, History, HISTORY_LIMIT};
+pub use location::{
+    config_dir, data_dir, open_dir_store, open_file_store, profile_names, try_open_store,
+    valid_profile_name,
+};
+pub use lock::{lock_for_write, try_lock_session, LOCK_TIMEOUT};
+pub use store::{merge_tasks, SqliteStore, Storage, TaskQuery, TaskStore, TsvStore};
+pub use task::{
+    extract_due, extract_priority, has_tag, lower_priority, new_task_id, parse_due,
+    parse_due_setting, parse_priority, parse_priority_setting, parse_tags, parse_task_ref,
+    priority_name, raise_priority, set_done, task_text, Priority, Task, TaskRef, ID_LENGTH,
+};
@@ -0,0 +1,187 @@
+//! Where lists live: the data and config directories, profiles and --file.
+
+use crate::lock::lock_for_write;
+use crate::store::{SqliteStore, TaskStore, TsvStore};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The named profile's store, else the one for `file` (from --file, else
+/// $PASTEL_TODO_FILE), else the store the config file asks for in the data
+/// directory.
+pub fn try_open_store(
+    file: Option<PathBuf>,
+    profile: Option<&str>,
+) -> Result<Box<dyn TaskStore>, String> {
+    if let Some(name) = profile {
+        let dir = data_dir()?.join("profiles").join(name);
+        fs::create_dir_all(&dir)
+            .map_err(|err| format!("cannot create {}: {err}", dir.display()))?;
+        return open_dir_store(&dir);
+    }
+    let file = file.or_else(|| {
+        env::var_os("PASTEL_TODO_FILE")
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    });
+    match file {
+        Some(path) => open_file_store(path),
+        None => {
+            let dir = data_dir()?;
+            move_legacy_files(&dir)?;
+            open_dir_store(&dir)
+        }
+    }
+}
+
+/// A task file named by the user: .db, .sqlite and .sqlite3 files are SQLite
+/// databases, anything else is TSV.
+pub fn open_file_store(path: PathBuf) -> Result<Box<dyn TaskStore>, String> {
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    if !dir.is_dir() {
+        return Err(format!(
+            "cannot use {}: directory {} does not exist",
+            path.display(),
+            dir.display()
+        ));
+    }
+    let extension = path.extension().and_then(|e| e.to_str());
+    if matches!(extension, Some("db" | "sqlite" | "sqlite3")) {
+        let store = SqliteStore::open(&path)
+            .map_err(|err| format!("cannot open {}: {err}", path.display()))?;
+        Ok(Box::new(store))
+    } else {
+        Ok(Box::new(TsvStore::new(path)))
+    }
+}
+
+/// tasks.tsv, or tasks.db with `store = sqlite`, in `dir`. The database
+/// imports the TSV list and history the first time it is used.
+pub fn open_dir_store(dir: &Path) -> Result<Box<dyn TaskStore>, String> {
+    let path = dir.join("tasks.tsv");
+    let backend = read_settings()
+        .into_iter()
+        .rev()
+        .find(|(key, _)| key == "store")
+        .map_or_else(|| "tsv".to_string(), |(_, value)| value.to_lowercase());
+    match backend.as_str() {
+        "tsv" => Ok(Box::new(TsvStore::new(path))),
+        "sqlite" => {
+            let db = dir.join("tasks.db");
+            if !db.exists() && path.exists() {
+                // Under the TSV list's lock, so a save to it cannot land
+                // half-way through, and only one instance imports.
+                let _lock = lock_for_write(&path)
+                    .map_err(|err| format!("cannot lock {}: {err}", path.display()))?;
+                if !db.exists() {
+                    let store = SqliteStore::create_from_tsv(&db, &path)
+                        .map_err(|err| format!("cannot import {}: {err}", path.display()))?;
+                    return Ok(Box::new(store));
+                }
+            }
+            let store = SqliteStore::open(&db)
+                .map_err(|err| format!("cannot open {}: {err}", db.display()))?;
+            Ok(Box::new(store))
+        }
+        other => Err(format!(
+            "unknown store '{other}' in config (expected tsv or sqlite)"
+        )),
+    }
+}
+
+/// Holds the config file; before tasks moved to the data directory they
+/// lived here too.
+pub fn config_dir() -> Option<PathBuf> {
+    dirs_next::config_dir().map(|dir| dir.join("pastel_todo"))
+}
+
+/// $XDG_DATA_HOME/pastel_todo (usually ~/.local/share/pastel_todo), or the
+/// platform's equivalent.
+pub fn data_dir() -> Result<PathBuf, String> {
+    let dir = dirs_next::data_dir()
+        .ok_or("cannot find a data directory; set PASTEL_TODO_FILE or pass --file")?
+        .join("pastel_todo");
+    fs::create_dir_all(&dir).map_err(|err| format!("cannot create {}: {err}", dir.display()))?;
+    Ok(dir)
+}
+
+/// Everything that used to sit next to tasks.tsv in the config directory.
+pub const DATA_FILES: &[&str] = &[
+    "tasks.tsv",
+    "tasks.db",
+    "history.tsv",
+    "tasks.rejected",
+    "tasks.v1.tsv",
+    "backups",
+];
+
+/// Moves the task files from the config directory into `dir`, once: only
+/// while `dir` has no list of its own.
+pub fn move_legacy_files(dir: &Path) -> Result<(), String> {
+    let Some(old) = config_dir() else {
+        return Ok(());
+    };
+    let has_list = ["tasks.tsv", "tasks.db"]
+        .iter()
+        .any(|name| dir.join(name).exists());
+    if old == dir || has_list {
+        return Ok(());
+    }
+    for name in DATA_FILES {
+        let from = old.join(name);
+        if from.exists() {
+            fs::rename(&from, dir.join(name)).map_err(|err| {
+                format!("cannot move {} to {}: {err}", from.display(), dir.display())
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Profile names become directory names, so they are kept to a safe set.
+/// "default" is not one: it names the default list, at the prompt and on
+/// the command line alike.
+pub fn valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "default"
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Profiles that have a directory under the data directory, sorted.
+pub fn profile_names() -> Vec<String> {
+    let Ok(dir) = data_dir() else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = fs::read_dir(dir.join("profiles"))
+        .map(|entries| {
+            entries
+                .map_while(Result::ok)
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| valid_profile_name(name))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// `key = value` lines from the config file. Blank lines and lines starting
+/// with # are ignored; a missing file means all defaults.
+pub fn read_settings() -> Vec<(String, String)> {
+    let content = config_dir()
+        .and_then(|dir| fs::read_to_string(dir.join("config")).ok())
+        .unwrap_or_default();
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_lowercase(), value.trim().to_string()))
+        .collect()
+}
@@ -0,0 +1,58 @@
+//! Advisory locks next to the stored list keep instances from overwriting
+//! each other. `<file>.lock` is held for a load, modify and save cycle: by
+//! the TUI while it handles a key, by the command line for a whole command
+//! that changes tasks. `<file>.session` is held by a TUI for as long as it
+//! runs; a second TUI on the same list cannot get it and stays read-only.
+
+use std::fs::{self, File, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long `lock_for_write` waits for another instance to finish saving.
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn lock_path(store_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = store_path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    store_path.with_file_name(name)
+}
+
+fn open_lock_file(path: &Path) -> io::Result<File> {
+    fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+}
+
+/// Takes the write lock on the list stored at `store_path`, waiting up to
+/// LOCK_TIMEOUT. Hold the returned file for the whole load, modify and save
+/// cycle; dropping it releases the lock.
+pub fn lock_for_write(store_path: &Path) -> io::Result<File> {
+    let file = open_lock_file(&lock_path(store_path, ".lock"))?;
+    let deadline = Instant::now() + LOCK_TIMEOUT;
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(file),
+            Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(TryLockError::WouldBlock) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "another instance is saving the list",
+                ))
+            }
+            Err(TryLockError::Error(err)) => return Err(err),
+        }
+    }
+}
+
+/// Takes the session lock without waiting; None when another TUI holds it.
+pub fn try_lock_session(store_path: &Path) -> Option<File> {
+    let file = open_lock_file(&lock_path(store_path, ".session")).ok()?;
+    file.try_lock().ok()?;
+    Some(file)
+}
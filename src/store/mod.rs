@@ -0,0 +1,360 @@
+//! The app keeps the whole list in memory and hands the store only what
+//! changed (see `diff_tasks`), so a backend that can write single records
+//! never rewrites the rest. `store = tsv` (the default) or `store = sqlite` in
+//! the config file picks the backend.
+
+use crate::history::{diff_tasks, Change, History};
+use crate::lock::{lock_for_write, try_lock_session};
+use crate::task::{has_tag, new_task_id, Task};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+mod sqlite;
+mod tsv;
+
+pub use sqlite::SqliteStore;
+pub use tsv::TsvStore;
+pub(crate) use tsv::{history_path, load_history, read_tasks};
+
+/// Where a task list and its undo history are kept.
+pub trait TaskStore {
+    /// The file the list is stored in; lock files are named after it.
+    fn path(&self) -> &Path;
+    /// The whole list in order. Problems that did not stop loading (such as
+    /// quarantined lines) are kept for `take_warnings`.
+    fn load(&mut self) -> io::Result<Vec<Task>>;
+    /// Inserts `task` at position `index` of the list.
+    fn insert(&mut self, index: usize, task: &Task) -> io::Result<()>;
+    /// Replaces the task with ID `id`.
+    fn update(&mut self, id: &str, task: &Task) -> io::Result<()>;
+    /// Deletes the task with ID `id`.
+    fn delete(&mut self, id: &str) -> io::Result<()>;
+    /// Throws away whatever is stored and writes `tasks` instead.
+    fn replace(&mut self, tasks: &[Task]) -> io::Result<()>;
+    /// The saved undo history; empty if there is none or it cannot be read.
+    fn load_history(&mut self) -> History;
+    /// Replaces the saved undo history.
+    fn save_history(&mut self, history: &History) -> io::Result<()>;
+
+    /// Warnings from the last `load`, each a line for the user. A heading
+    /// line starts with ⚠, the lines under it are indented.
+    fn take_warnings(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Whether something else (another instance, a script, an editor) has
+    /// changed the stored list since this store last loaded or wrote it.
+    fn changed_elsewhere(&mut self) -> bool {
+        false
+    }
+
+    /// The tasks `query` matches, in order. Backends that can select
+    /// records override it; the default loads everything and filters.
+    fn query(&mut self, query: &TaskQuery) -> io::Result<Vec<Task>> {
+        let mut tasks = self.load()?;
+        tasks.retain(|t| query.matches(t));
+        Ok(tasks)
+    }
+
+    /// Stores `changes`, made to the list as last loaded or written, in
+    /// order. The default makes one call per change.
+    fn apply(&mut self, changes: &[Change]) -> io::Result<()> {
+        apply_each(self, changes)
+    }
+}
+
+/// Which tasks `TaskStore::query` returns: those in `folder` (any folder when
+/// None) that carry `tag`, matched like the TUI filter.
+pub struct TaskQuery {
+    /// Only tasks in this folder.
+    pub folder: Option<String>,
+    /// Only tasks with this tag (`#tag`, `+project`, or either without the
+    /// sigil).
+    pub tag: Option<String>,
+}
+
+impl TaskQuery {
+    /// Whether `task` is one the query asks for.
+    pub fn matches(&self, task: &Task) -> bool {
+        self.folder.as_ref().is_none_or(|f| task.folder == *f)
+            && self.tag.as_ref().is_none_or(|tag| has_tag(task, tag))
+    }
+}
+
+pub(crate) fn apply_each<S: TaskStore + ?Sized>(
+    store: &mut S,
+    changes: &[Change],
+) -> io::Result<()> {
+    for change in changes {
+        match change {
+            Change::Insert { index, task } => store.insert(*index, task)?,
+            Change::Remove { task, .. } => store.delete(&task.id)?,
+            Change::Update { before, after, .. } => store.update(&before.id, after)?,
+        }
+    }
+    Ok(())
+}
+
+/// The task store together with the undo history saved into it.
+pub struct Storage {
+    /// Where the list is kept.
+    pub store: Box<dyn TaskStore>,
+    /// The undo history, saved to the store with every change.
+    pub history: History,
+    /// The --file list, which stands in for the default profile.
+    pub file: Option<PathBuf>,
+    /// The list as last loaded or successfully saved; the common ancestor
+    /// when merging in changes made elsewhere.
+    pub synced: Vec<Task>,
+    // The TUI's session lock on the store, if it got one (see `lock_session`).
+    session: Option<File>,
+}
+
+impl Storage {
+    /// Wraps a store whose list was just loaded as `tasks`, loading its
+    /// history. `file` is the --file list, if one was given.
+    pub fn new(mut store: Box<dyn TaskStore>, tasks: &[Task], file: Option<PathBuf>) -> Self {
+        let history = store.load_history();
+        Storage {
+            store,
+            history,
+            file,
+            synced: tasks.to_vec(),
+            session: None,
+        }
+    }
+
+    /// Takes the session lock on the current store. False when another TUI
+    /// holds it (or it cannot be created), meaning this one is read-only.
+    pub fn lock_session(&mut self) -> bool {
+        self.session = try_lock_session(self.store.path());
+        self.session.is_some()
+    }
+
+    /// The write lock for one load, modify and save cycle; released on drop.
+    pub fn lock(&self) -> io::Result<File> {
+        lock_for_write(self.store.path())
+    }
+
+    /// Stores what changed from `before` to `tasks`, then the undo history.
+    /// After a failed save (`resync`) the store may be behind by more than
+    /// that, so it gets the whole list instead. Returns a message for the UI
+    /// or CLI when either write fails.
+    pub fn save(&mut self, before: &[Task], tasks: &[Task], resync: bool) -> Option<String> {
+        let saved = if resync {
+            self.store.replace(tasks)
+        } else {
+            self.store.apply(&diff_tasks(before, tasks))
+        };
+        if let Err(err) = saved {
+            return Some(format!("Could not save tasks: {err}"));
+        }
+        self.synced = tasks.to_vec();
+        self.store
+            .save_history(&self.history)
+            .err()
+            .map(|err| format!("Could not save history: {err}"))
+    }
+}
+
+/// Three-way merge by task ID of the list on screen (`ours`) and the stored
+/// one (`theirs`), both descended from `base`. A change on one side wins.
+/// Nothing is dropped on a conflict: when both sides changed a task, both
+/// versions are kept (ours under a new ID), and a task changed on one side
+/// and deleted on the other stays. Returns one warning line per conflict.
+pub fn merge_tasks(base: &[Task], ours: &[Task], theirs: &[Task]) -> (Vec<Task>, Vec<String>) {
+    if ours == base {
+        return (theirs.to_vec(), Vec::new());
+    }
+    let base_by_id: HashMap<&str, &Task> = base.iter().map(|t| (t.id.as_str(), t)).collect();
+    let ours_by_id: HashMap<&str, &Task> = ours.iter().map(|t| (t.id.as_str(), t)).collect();
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+    // Every ID a copy of ours must avoid: both lists and earlier copies.
+    let mut taken: Vec<Task> = ours.iter().chain(theirs).cloned().collect();
+    for t in theirs {
+        match (base_by_id.get(t.id.as_str()), ours_by_id.get(t.id.as_str())) {
+            (Some(&b), Some(&o)) if o != b && t != b && t != o => {
+                let mut copy = o.clone();
+                copy.id = new_task_id(&taken);
+                taken.push(copy.clone());
+                conflicts.push(format!(
+                    "  {}: kept both versions (yours is now {})",
+                    t.id, copy.id
+                ));
+                merged.push(t.clone());
+                merged.push(copy);
+            }
+            (Some(&b), Some(&o)) if o != b => merged.push(o.clone()),
+            (Some(&b), None) if t != b => {
+                conflicts.push(format!("  {}: changed elsewhere, so not deleted", t.id));
+                merged.push(t.clone());
+            }
+            // Deleted here and untouched elsewhere.
+            (Some(_), None) => {}
+            _ => merged.push(t.clone()),
+        }
+    }
+    let theirs_ids: HashSet<&str> = theirs.iter().map(|t| t.id.as_str()).collect();
+    for o in ours.iter().filter(|o| !theirs_ids.contains(o.id.as_str())) {
+        match base_by_id.get(o.id.as_str()) {
+            None => merged.push(o.clone()),
+            Some(&b) if o != b => {
+                conflicts.push(format!("  {}: deleted elsewhere, kept your changes", o.id));
+                merged.push(o.clone());
+            }
+            Some(_) => {}
+        }
+    }
+    (merged, conflicts)
+}
+
+/// A file kept next to the list at `path`, named after it: `tasks.tsv` has
+/// `tasks.rejected`, `todo.tsv` has `todo.rejected`. Two lists in one
+/// directory so never share one.
+pub(crate) fn companion_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = list_stem(path).to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+// The list's file name without its extension: "tasks" for tasks.tsv.
+pub(crate) fn list_stem(path: &Path) -> &OsStr {
+    path.file_stem().unwrap_or_default()
+}
+
+pub(crate) fn not_found(id: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("no task with ID {id}"))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use chrono::{DateTime, Local};
+    use std::fs;
+
+    // An open task in the inbox. Equal arguments give equal tasks.
+    pub(crate) fn task(id: &str, text: &str) -> Task {
+        Task {
+            id: id.to_string(),
+            text: text.to_string(),
+            done: false,
+            folder: "inbox".to_string(),
+            created_at: DateTime::parse_from_rfc3339("2026-10-17T12:00:00+02:00")
+                .unwrap()
+                .with_timezone(&Local),
+            completed_at: None,
+            due: None,
+            priority: None,
+            tags: Vec::new(),
+        }
+    }
+
+    // An empty directory of its own under the system temp directory.
+    pub(crate) fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pastel_todo-test-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn texts(tasks: &[Task]) -> Vec<&str> {
+        tasks.iter().map(|t| t.text.as_str()).collect()
+    }
+
+    fn edited(tasks: &[Task], suffix: &str) -> Vec<Task> {
+        tasks
+            .iter()
+            .map(|t| task(&t.id, &format!("{}{suffix}", t.text)))
+            .collect()
+    }
+
+    #[test]
+    fn merge_takes_the_side_that_changed() {
+        let base = vec![task("a", "one"), task("b", "two"), task("c", "three")];
+        let ours = vec![
+            task("a", "one, edited here"),
+            task("b", "two"),
+            task("d", "four"),
+        ];
+        let theirs = vec![
+            task("a", "one"),
+            task("b", "two, edited there"),
+            task("e", "five"),
+        ];
+        let (merged, conflicts) = merge_tasks(&base, &ours, &theirs);
+        // c was deleted on both sides; d and e were added on one each.
+        assert_eq!(
+            texts(&merged),
+            ["one, edited here", "two, edited there", "five", "four"]
+        );
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn merge_without_local_changes_takes_theirs() {
+        let base = vec![task("a", "one"), task("b", "two")];
+        let theirs = vec![task("b", "two, edited there")];
+        let (merged, conflicts) = merge_tasks(&base, &base, &theirs);
+        assert_eq!(merged, theirs);
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn merge_keeps_both_versions_of_a_task_changed_on_both_sides() {
+        let base = vec![task("a", "one")];
+        let ours = vec![task("a", "one, edited here")];
+        let theirs = vec![task("a", "one, edited there")];
+        let (merged, conflicts) = merge_tasks(&base, &ours, &theirs);
+        assert_eq!(texts(&merged), ["one, edited there", "one, edited here"]);
+        assert_eq!(merged[0].id, "a");
+        assert_ne!(merged[1].id, "a");
+        assert_eq!(conflicts.len(), 1);
+    }
+
+    #[test]
+    fn merge_keeps_a_task_changed_on_one_side_and_deleted_on_the_other() {
+        let base = vec![task("a", "one"), task("b", "two"), task("c", "three")];
+        // a: changed here, deleted there. b: deleted here, changed there.
+        // c: deleted here, untouched there, so it goes.
+        let ours = vec![task("a", "one, edited here")];
+        let theirs = vec![task("b", "two, edited there"), task("c", "three")];
+        let (merged, conflicts) = merge_tasks(&base, &ours, &theirs);
+        assert_eq!(texts(&merged), ["two, edited there", "one, edited here"]);
+        assert_eq!(conflicts.len(), 2);
+    }
+
+    #[test]
+    fn conflict_copies_get_ids_used_nowhere_else() {
+        let base: Vec<Task> = (0..40).map(|i| task(&format!("t{i}"), "task")).collect();
+        let mut ours = edited(&base, " (ours)");
+        ours.push(task("only-ours", "added here"));
+        let theirs = edited(&base, " (theirs)");
+        let (merged, conflicts) = merge_tasks(&base, &ours, &theirs);
+        assert_eq!(conflicts.len(), 40);
+        assert_eq!(merged.len(), 81);
+        let ids: HashSet<&str> = merged.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids.len(), merged.len());
+    }
+
+    #[test]
+    fn companion_files_are_named_after_the_list() {
+        let dir = Path::new("/lists");
+        assert_eq!(
+            companion_path(&dir.join("tasks.tsv"), ".rejected"),
+            dir.join("tasks.rejected")
+        );
+        assert_eq!(
+            companion_path(&dir.join("work.db"), ".history.tsv"),
+            dir.join("work.history.tsv")
+        );
+        assert_eq!(
+            companion_path(&dir.join("todo"), ".v1.tsv"),
+            dir.join("todo.v1.tsv")
+        );
+    }
+}
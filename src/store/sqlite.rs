@@ -0,0 +1,436 @@
+//! The SQLite backend: tasks.db.
+
+use super::{apply_each, history_path, load_history, not_found, read_tasks, TaskQuery, TaskStore};
+use crate::format::FORMAT_VERSION;
+use crate::history::{format_history, parse_history, Change, History};
+use crate::task::{parse_priority, priority_name, Task};
+use chrono::{DateTime, Local, NaiveDate};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// tasks.db: one row per task, ordered by `position` (0, 1, 2, ... like the
+/// list). The history table holds the same lines as tasks.history.tsv, in the
+/// format named by `user_version`.
+pub struct SqliteStore {
+    path: PathBuf,
+    conn: rusqlite::Connection,
+    // SQLite's counter of commits by other connections, as of the last load.
+    data_version: i64,
+    // Lines of an imported TSV list that could not be read.
+    warnings: Vec<String>,
+}
+
+const SQLITE_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS tasks (
+        id TEXT PRIMARY KEY,
+        position INTEGER NOT NULL,
+        done INTEGER NOT NULL,
+        folder TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        completed_at TEXT,
+        due TEXT,
+        priority TEXT,
+        tags TEXT NOT NULL,
+        text TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS tasks_position ON tasks (position);
+    CREATE INDEX IF NOT EXISTS tasks_folder ON tasks (folder);
+    CREATE TABLE IF NOT EXISTS history (
+        seq INTEGER PRIMARY KEY,
+        line TEXT NOT NULL
+    );";
+
+const SQLITE_COLUMNS: &str =
+    "id, done, folder, created_at, completed_at, due, priority, tags, text";
+
+fn sql_error(err: rusqlite::Error) -> io::Error {
+    io::Error::other(err)
+}
+
+impl SqliteStore {
+    /// Opens the database at `path`, creating it and its tables if needed.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let conn = rusqlite::Connection::open(path).map_err(sql_error)?;
+        let version: u32 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(sql_error)?;
+        if version > FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "format v{version} is newer than this build understands (v{FORMAT_VERSION})"
+                ),
+            ));
+        }
+        conn.execute_batch(SQLITE_SCHEMA).map_err(sql_error)?;
+        conn.pragma_update(None, "user_version", FORMAT_VERSION)
+            .map_err(sql_error)?;
+        let mut store = SqliteStore {
+            path: path.to_path_buf(),
+            conn,
+            data_version: 0,
+            warnings: Vec::new(),
+        };
+        store.data_version = store.data_version().map_err(sql_error)?;
+        Ok(store)
+    }
+
+    fn data_version(&self) -> rusqlite::Result<i64> {
+        self.conn
+            .query_row("PRAGMA data_version", [], |row| row.get(0))
+    }
+
+    /// Creates the database at `path` from a TSV list and its undo history.
+    /// It is built under a temporary name and only renamed into place once
+    /// complete, so a failed import leaves no half-filled database behind.
+    /// The TSV files are only read; lines that cannot be read are left out
+    /// and reported by `take_warnings`.
+    pub fn create_from_tsv(path: &Path, tsv: &Path) -> io::Result<Self> {
+        let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(".import");
+        let tmp = path.with_file_name(tmp_name);
+        let imported = (|| {
+            let read = read_tasks(tsv)?;
+            let _ = fs::remove_file(&tmp);
+            let mut store = SqliteStore::open(&tmp)?;
+            store.replace(&read.tasks)?;
+            store.save_history(&load_history(&history_path(tsv)))?;
+            drop(store);
+            fs::rename(&tmp, path)?;
+            Ok(read.rejected_warnings(&format!(" of {} not imported", tsv.display())))
+        })();
+        let warnings = match imported {
+            Ok(warnings) => warnings,
+            Err(err) => {
+                let _ = fs::remove_file(&tmp);
+                return Err(err);
+            }
+        };
+        let mut store = SqliteStore::open(path)?;
+        store.warnings = warnings;
+        Ok(store)
+    }
+
+    fn select<P: rusqlite::Params>(&self, condition: &str, params: P) -> io::Result<Vec<Task>> {
+        let mut statement = self
+            .conn
+            .prepare(&format!(
+                "SELECT {SQLITE_COLUMNS} FROM tasks {condition} ORDER BY position"
+            ))
+            .map_err(sql_error)?;
+        let rows = statement
+            .query_map(params, |row| {
+                Ok([
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?.to_string(),
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                    row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                    row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+                    row.get(7)?,
+                    row.get(8)?,
+                ])
+            })
+            .map_err(sql_error)?;
+        let mut tasks = Vec::new();
+        for row in rows {
+            let row = row.map_err(sql_error)?;
+            let task = task_from_columns(&row).map_err(|reason| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("task {}: {reason}", row[0]),
+                )
+            })?;
+            tasks.push(task);
+        }
+        Ok(tasks)
+    }
+
+    fn write_task(&self, position: usize, task: &Task) -> io::Result<()> {
+        self.conn
+            .execute(
+                &format!(
+                    "INSERT INTO tasks (position, {SQLITE_COLUMNS}) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"
+                ),
+                rusqlite::params![
+                    position as i64,
+                    task.id,
+                    task.done,
+                    task.folder,
+                    task.created_at.to_rfc3339(),
+                    task.completed_at.map(|t| t.to_rfc3339()),
+                    task.due.map(|d| d.format("%Y-%m-%d").to_string()),
+                    task.priority.map(priority_name),
+                    task.tags.join(","),
+                    task.text,
+                ],
+            )
+            .map_err(sql_error)?;
+        Ok(())
+    }
+
+    fn position(&self, id: &str) -> io::Result<usize> {
+        let position: Option<i64> = self
+            .conn
+            .query_row("SELECT position FROM tasks WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .map(Some)
+            .or_else(|err| match err {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                err => Err(err),
+            })
+            .map_err(sql_error)?;
+        position.map(|p| p as usize).ok_or_else(|| not_found(id))
+    }
+
+    // Runs `f` in a transaction, so a failure leaves the database as it was.
+    fn in_transaction(&mut self, f: impl FnOnce(&mut Self) -> io::Result<()>) -> io::Result<()> {
+        self.conn.execute_batch("BEGIN").map_err(sql_error)?;
+        let result = f(self);
+        let end = if result.is_ok() { "COMMIT" } else { "ROLLBACK" };
+        let ended = self.conn.execute_batch(end).map_err(sql_error);
+        result.and(ended)
+    }
+}
+
+// Columns as selected by `SqliteStore::select`, all as text.
+fn task_from_columns(columns: &[String; 9]) -> Result<Task, String> {
+    let [id, done, folder, created_at, completed_at, due, priority, tags, text] = columns;
+    let created_at = DateTime::parse_from_rfc3339(created_at)
+        .map_err(|_| format!("bad creation time '{created_at}'"))?
+        .with_timezone(&Local);
+    let completed_at = if completed_at.is_empty() {
+        None
+    } else {
+        let time = DateTime::parse_from_rfc3339(completed_at)
+            .map_err(|_| format!("bad completion time '{completed_at}'"))?;
+        Some(time.with_timezone(&Local))
+    };
+    let due = if due.is_empty() {
+        None
+    } else {
+        let date = NaiveDate::parse_from_str(due, "%Y-%m-%d")
+            .map_err(|_| format!("bad due date '{due}'"))?;
+        Some(date)
+    };
+    let priority = if priority.is_empty() {
+        None
+    } else {
+        Some(parse_priority(priority).ok_or(format!("unknown priority '{priority}'"))?)
+    };
+    let tags = if tags.is_empty() {
+        Vec::new()
+    } else {
+        tags.split(',').map(str::to_string).collect()
+    };
+    Ok(Task {
+        id: id.clone(),
+        text: text.clone(),
+        done: done != "0",
+        folder: folder.clone(),
+        created_at,
+        completed_at,
+        due,
+        priority,
+        tags,
+    })
+}
+
+impl TaskStore for SqliteStore {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&mut self) -> io::Result<Vec<Task>> {
+        self.data_version = self.data_version().map_err(sql_error)?;
+        self.select("", [])
+    }
+
+    fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn changed_elsewhere(&mut self) -> bool {
+        self.data_version()
+            .is_ok_and(|version| version != self.data_version)
+    }
+
+    fn insert(&mut self, index: usize, task: &Task) -> io::Result<()> {
+        self.conn
+            .execute(
+                "UPDATE tasks SET position = position + 1 WHERE position >= ?1",
+                [index as i64],
+            )
+            .map_err(sql_error)?;
+        self.write_task(index, task)
+    }
+
+    fn update(&mut self, id: &str, task: &Task) -> io::Result<()> {
+        let position = self.position(id)?;
+        self.conn
+            .execute("DELETE FROM tasks WHERE id = ?1", [id])
+            .map_err(sql_error)?;
+        self.write_task(position, task)
+    }
+
+    fn delete(&mut self, id: &str) -> io::Result<()> {
+        let position = self.position(id)?;
+        self.conn
+            .execute("DELETE FROM tasks WHERE id = ?1", [id])
+            .map_err(sql_error)?;
+        self.conn
+            .execute(
+                "UPDATE tasks SET position = position - 1 WHERE position > ?1",
+                [position as i64],
+            )
+            .map_err(sql_error)?;
+        Ok(())
+    }
+
+    fn replace(&mut self, tasks: &[Task]) -> io::Result<()> {
+        self.in_transaction(|store| {
+            store
+                .conn
+                .execute("DELETE FROM tasks", [])
+                .map_err(sql_error)?;
+            for (position, task) in tasks.iter().enumerate() {
+                store.write_task(position, task)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn load_history(&mut self) -> History {
+        let version: u32 = match self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+        {
+            Ok(version) => version,
+            Err(_) => return History::default(),
+        };
+        let lines: rusqlite::Result<Vec<String>> = self
+            .conn
+            .prepare("SELECT line FROM history ORDER BY seq")
+            .and_then(|mut statement| statement.query_map([], |row| row.get(0))?.collect());
+        match lines {
+            Ok(lines) => parse_history(lines.iter().map(String::as_str), version),
+            Err(_) => History::default(),
+        }
+    }
+
+    fn save_history(&mut self, history: &History) -> io::Result<()> {
+        let lines = format_history(history);
+        self.in_transaction(|store| {
+            store
+                .conn
+                .execute("DELETE FROM history", [])
+                .map_err(sql_error)?;
+            for line in &lines {
+                store
+                    .conn
+                    .execute("INSERT INTO history (line) VALUES (?1)", [line])
+                    .map_err(sql_error)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn query(&mut self, query: &TaskQuery) -> io::Result<Vec<Task>> {
+        let mut tasks = match &query.folder {
+            Some(folder) => self.select("WHERE folder = ?1", [folder])?,
+            None => self.select("", [])?,
+        };
+        tasks.retain(|t| query.matches(t));
+        Ok(tasks)
+    }
+
+    fn apply(&mut self, changes: &[Change]) -> io::Result<()> {
+        self.in_transaction(|store| apply_each(store, changes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{apply_changes, diff_tasks};
+    use crate::store::tests::{scratch_dir, task};
+    use crate::task::Priority;
+
+    #[test]
+    fn tasks_come_back_as_stored() {
+        let dir = scratch_dir("sqlite-round-trip");
+        let mut odd = task("bcdefg", "tab\there, line\nbreak and \\ #tag");
+        odd.folder = "a\tfolder".to_string();
+        odd.due = NaiveDate::from_ymd_opt(2026, 11, 1);
+        odd.priority = Some(Priority::Low);
+        odd.tags = vec!["#tag".to_string()];
+        let tasks = vec![task("abcdef", "plain"), odd];
+
+        let mut store = SqliteStore::open(&dir.join("tasks.db")).unwrap();
+        store.replace(&tasks).unwrap();
+        assert_eq!(store.load().unwrap(), tasks);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn changes_are_stored_and_undone() {
+        let dir = scratch_dir("sqlite-apply");
+        let before = vec![task("a", "one"), task("b", "two"), task("c", "three")];
+        let after = vec![
+            task("n", "new"),
+            task("b", "two, edited"),
+            task("c", "three"),
+        ];
+        let changes = diff_tasks(&before, &after);
+
+        let mut store = SqliteStore::open(&dir.join("tasks.db")).unwrap();
+        store.replace(&before).unwrap();
+        store.apply(&changes).unwrap();
+        assert_eq!(store.load().unwrap(), after);
+
+        let mut undone = after.clone();
+        assert!(apply_changes(&mut undone, &changes, false));
+        let reverse = diff_tasks(&after, &undone);
+        store.apply(&reverse).unwrap();
+        assert_eq!(store.load().unwrap(), before);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn import_reads_the_tsv_list_without_changing_it() {
+        let dir = scratch_dir("sqlite-import");
+        let tsv = dir.join("tasks.tsv");
+        let original = "0\tinbox\t2026-10-17T12:15:58+02:00\tcheck\n0\tinbox\n";
+        fs::write(&tsv, original).unwrap();
+
+        let db = dir.join("tasks.db");
+        let mut store = SqliteStore::create_from_tsv(&db, &tsv).unwrap();
+        let tasks = store.load().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].text, "check");
+        assert_eq!(store.take_warnings().len(), 2);
+        assert_eq!(fs::read_to_string(&tsv).unwrap(), original);
+        assert!(!dir.join("tasks.rejected").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn failed_import_leaves_no_database() {
+        let dir = scratch_dir("sqlite-import-failed");
+        let tsv = dir.join("tasks.tsv");
+        fs::write(
+            &tsv,
+            format!("# pastel_todo tasks v{}\n", FORMAT_VERSION + 1),
+        )
+        .unwrap();
+
+        let db = dir.join("tasks.db");
+        assert!(SqliteStore::create_from_tsv(&db, &tsv).is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,554 @@
+//! The plain-text backend: tasks.tsv and tasks.history.tsv.
+
+use super::{companion_path, list_stem, not_found, TaskStore};
+use crate::format::{
+    format_task_line, format_version, parse_task_line, FORMAT_VERSION, HISTORY_HEADER, TASKS_HEADER,
+};
+use crate::history::{apply_changes, format_history, parse_history, Change, History};
+use crate::task::{new_task_id, Task};
+use chrono::{Local, NaiveDateTime};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// tasks.tsv is copied into tasks.backups/ at most this often, keeping the newest
+// BACKUP_LIMIT copies.
+const BACKUP_INTERVAL_SECS: i64 = 60 * 60;
+
+const BACKUP_LIMIT: usize = 10;
+
+/// tasks.tsv plus tasks.history.tsv next to it (each file next to the list
+/// takes its name from the list's). Every change rewrites the whole file, so
+/// it keeps a copy of the list to write out. Lines that cannot be read are
+/// moved to tasks.rejected, and the file is copied to tasks.backups/ at most
+/// once an hour before it is replaced.
+pub struct TsvStore {
+    path: PathBuf,
+    tasks: Vec<Task>,
+    warnings: Vec<String>,
+    // Modification time and size of the file as this store left it.
+    stamp: Option<(SystemTime, u64)>,
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+impl TsvStore {
+    /// A store for the TSV file at `path`, which need not exist yet.
+    pub fn new(path: PathBuf) -> Self {
+        TsvStore {
+            path,
+            tasks: Vec::new(),
+            warnings: Vec::new(),
+            stamp: None,
+        }
+    }
+
+    fn write(&mut self) -> io::Result<()> {
+        let backup_warning = save_tasks(&self.path, &self.tasks)?;
+        self.warnings.extend(backup_warning);
+        self.stamp = file_stamp(&self.path);
+        Ok(())
+    }
+
+    fn position(&self, id: &str) -> io::Result<usize> {
+        self.tasks
+            .iter()
+            .position(|t| t.id == id)
+            .ok_or_else(|| not_found(id))
+    }
+}
+
+/// The undo history of the list at `path`: tasks.history.tsv for tasks.tsv.
+/// Lists used to share history.tsv; the default list takes it over the
+/// first time it is used.
+pub(crate) fn history_path(path: &Path) -> PathBuf {
+    let history = companion_path(path, ".history.tsv");
+    let legacy = path.with_file_name("history.tsv");
+    if list_stem(path) == "tasks" && !history.exists() && legacy.exists() {
+        let _ = fs::rename(&legacy, &history);
+    }
+    history
+}
+
+impl TaskStore for TsvStore {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&mut self) -> io::Result<Vec<Task>> {
+        let (tasks, warnings) = load_tasks(&self.path)?;
+        self.tasks = tasks.clone();
+        self.warnings = warnings;
+        self.stamp = file_stamp(&self.path);
+        Ok(tasks)
+    }
+
+    fn insert(&mut self, index: usize, task: &Task) -> io::Result<()> {
+        self.tasks.insert(index.min(self.tasks.len()), task.clone());
+        self.write()
+    }
+
+    fn update(&mut self, id: &str, task: &Task) -> io::Result<()> {
+        let i = self.position(id)?;
+        self.tasks[i] = task.clone();
+        self.write()
+    }
+
+    fn delete(&mut self, id: &str) -> io::Result<()> {
+        let i = self.position(id)?;
+        self.tasks.remove(i);
+        self.write()
+    }
+
+    fn replace(&mut self, tasks: &[Task]) -> io::Result<()> {
+        self.tasks = tasks.to_vec();
+        self.write()
+    }
+
+    fn load_history(&mut self) -> History {
+        load_history(&history_path(&self.path))
+    }
+
+    fn save_history(&mut self, history: &History) -> io::Result<()> {
+        save_history(&history_path(&self.path), history)
+    }
+
+    fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn changed_elsewhere(&mut self) -> bool {
+        file_stamp(&self.path) != self.stamp
+    }
+
+    // One rewrite for the lot rather than one per change, and none (so no
+    // backup either) when nothing changed.
+    fn apply(&mut self, changes: &[Change]) -> io::Result<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        if !apply_changes(&mut self.tasks, changes, true) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the task file no longer matches the list",
+            ));
+        }
+        self.write()
+    }
+}
+
+/// Writes the list, backing up the file it replaces first. The backup is
+/// only a convenience: if it fails the list is written anyway, and the
+/// returned warning says why there is no backup.
+fn save_tasks(path: &Path, tasks: &[Task]) -> io::Result<Option<String>> {
+    let mut f = Vec::new();
+    writeln!(f, "{TASKS_HEADER}{FORMAT_VERSION}")?;
+    for t in tasks {
+        writeln!(f, "{}", format_task_line(t))?;
+    }
+    let backup_warning = backup_tasks(path)
+        .err()
+        .map(|err| format!("⚠ Could not back up {}: {err}", path.display()));
+    write_atomic(path, &f)?;
+    Ok(backup_warning)
+}
+
+fn save_history(path: &Path, history: &History) -> io::Result<()> {
+    let mut f = Vec::new();
+    writeln!(f, "{HISTORY_HEADER}{FORMAT_VERSION}")?;
+    for line in format_history(history) {
+        writeln!(f, "{line}")?;
+    }
+    write_atomic(path, &f)
+}
+
+/// A history file that cannot be read back is dropped rather than trusted.
+pub(crate) fn load_history(path: &Path) -> History {
+    let Ok(content) = fs::read_to_string(path) else {
+        return History::default();
+    };
+    let mut lines = content.lines().peekable();
+    let version = match lines.peek().and_then(|l| format_version(l, HISTORY_HEADER)) {
+        Some(version) => {
+            lines.next();
+            version
+        }
+        None => 1,
+    };
+    parse_history(lines, version)
+}
+
+// Writes to a temp file next to `path`, fsyncs it and renames it over `path`,
+// so a crash or a full disk leaves either the old file or the new one.
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+    if let Err(err) = write_synced(&tmp, contents).and_then(|()| fs::rename(&tmp, path)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(err);
+    }
+    // Make the rename itself durable; not every platform can open a directory.
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut f = File::create(path)?;
+    f.write_all(contents)?;
+    f.sync_all()
+}
+
+/// Copies the current file to tasks.backups/tasks-YYYYMMDD-HHMMSS.tsv (both
+/// named after the list) before it is replaced, unless the newest backup is
+/// younger than BACKUP_INTERVAL_SECS. Backups used to go to a shared backups/;
+/// the default list takes it over the first time it is backed up.
+fn backup_tasks(path: &Path) -> io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let dir = companion_path(path, ".backups");
+    let legacy = path.with_file_name("backups");
+    if list_stem(path) == "tasks" && !dir.exists() && legacy.is_dir() {
+        let _ = fs::rename(&legacy, &dir);
+    }
+    fs::create_dir_all(&dir)?;
+    let prefix = format!("{}-", list_stem(path).to_string_lossy());
+    let mut backups: Vec<(NaiveDateTime, PathBuf)> = fs::read_dir(&dir)?
+        .map_while(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let stamp = name.strip_prefix(&prefix)?;
+            let taken = NaiveDateTime::parse_from_str(stamp, "%Y%m%d-%H%M%S.tsv").ok()?;
+            Some((taken, entry.path()))
+        })
+        .collect();
+    backups.sort();
+
+    let now = Local::now().naive_local();
+    if let Some((latest, _)) = backups.last() {
+        if (now - *latest).num_seconds() < BACKUP_INTERVAL_SECS {
+            return Ok(());
+        }
+    }
+    let target = dir.join(format!("{prefix}{}.tsv", now.format("%Y%m%d-%H%M%S")));
+    fs::copy(path, &target)?;
+    backups.push((now, target));
+    let excess = backups.len().saturating_sub(BACKUP_LIMIT);
+    for (_, old) in &backups[..excess] {
+        let _ = fs::remove_file(old);
+    }
+    Ok(())
+}
+
+/// A line of tasks.tsv that could not be loaded, kept byte for byte.
+struct RejectedLine {
+    number: usize,
+    reason: String,
+    raw: Vec<u8>,
+}
+
+/// What `read_tasks` found in a task file, before anything is repaired.
+pub(crate) struct ReadTasks {
+    pub(crate) tasks: Vec<Task>,
+    rejected: Vec<RejectedLine>,
+    version: u32,
+    // Some tasks had no ID, or repeated one, and were given a fresh one.
+    assigned_ids: bool,
+}
+
+impl ReadTasks {
+    /// Warning lines for the lines that could not be read: a heading saying
+    /// what became of them (`outcome`), then one line each.
+    pub(crate) fn rejected_warnings(&self, outcome: &str) -> Vec<String> {
+        if self.rejected.is_empty() {
+            return Vec::new();
+        }
+        let plural = if self.rejected.len() == 1 { "" } else { "s" };
+        let mut warnings = vec![format!(
+            "⚠ {} unreadable line{plural}{outcome}:",
+            self.rejected.len()
+        )];
+        for line in &self.rejected {
+            warnings.push(format!("  line {}: {}", line.number, line.reason));
+        }
+        warnings
+    }
+}
+
+/// Loads the task list. Lines that do not parse are moved to tasks.rejected
+/// rather than dropped; the returned warnings (a heading, then one entry per
+/// line) say what happened. Older files are upgraded in place, so callers
+/// hold the write lock.
+fn load_tasks(path: &Path) -> io::Result<(Vec<Task>, Vec<String>)> {
+    let read = read_tasks(path)?;
+    let version = read.version;
+
+    // The file is rewritten (upgraded, or without the bad lines) only once
+    // the originals are safe elsewhere.
+    let mut warnings = Vec::new();
+    let mut safe_to_rewrite = true;
+    if version < FORMAT_VERSION {
+        let original = companion_path(path, &format!(".v{version}.tsv"));
+        if !original.exists() {
+            safe_to_rewrite = fs::copy(path, &original).is_ok();
+        }
+    }
+    if !read.rejected.is_empty() {
+        let rejected_path = companion_path(path, ".rejected");
+        let rejected_name = rejected_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let outcome = match quarantine_lines(&rejected_path, &read.rejected) {
+            Ok(()) => format!(" moved to {rejected_name}"),
+            Err(err) => {
+                safe_to_rewrite = false;
+                format!(", not saved to {rejected_name} ({err})")
+            }
+        };
+        warnings.extend(read.rejected_warnings(&outcome));
+    }
+    let repaired = version < FORMAT_VERSION || !read.rejected.is_empty() || read.assigned_ids;
+    if safe_to_rewrite && repaired {
+        match save_tasks(path, &read.tasks) {
+            Ok(backup_warning) => warnings.extend(backup_warning),
+            Err(err) => warnings.push(format!("⚠ Could not save tasks: {err}")),
+        }
+    }
+    Ok((read.tasks, warnings))
+}
+
+/// Reads a task file without changing it or anything next to it.
+pub(crate) fn read_tasks(path: &Path) -> io::Result<ReadTasks> {
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Ok(ReadTasks {
+                tasks: Vec::new(),
+                rejected: Vec::new(),
+                version: FORMAT_VERSION,
+                assigned_ids: false,
+            })
+        }
+        Err(err) => {
+            return Err(io::Error::new(
+                err.kind(),
+                format!("cannot read {}: {err}", path.display()),
+            ))
+        }
+    };
+    let mut lines = content
+        .split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .enumerate()
+        .peekable();
+    let header = lines
+        .peek()
+        .and_then(|(_, line)| std::str::from_utf8(line).ok())
+        .and_then(|line| format_version(line, TASKS_HEADER));
+    let version = match header {
+        Some(version) => {
+            lines.next();
+            version
+        }
+        None => 1,
+    };
+    if version > FORMAT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} uses format v{version}, newer than this build understands (v{FORMAT_VERSION})",
+                path.display()
+            ),
+        ));
+    }
+
+    let mut tasks = Vec::new();
+    let mut rejected = Vec::new();
+    for (i, raw) in lines {
+        if raw.is_empty() {
+            continue;
+        }
+        let parsed = match std::str::from_utf8(raw) {
+            Ok(line) => parse_task_line(line, version),
+            Err(_) => Err("not valid UTF-8".to_string()),
+        };
+        match parsed {
+            Ok(task) => tasks.push(task),
+            Err(reason) => rejected.push(RejectedLine {
+                number: i + 1,
+                reason,
+                raw: raw.to_vec(),
+            }),
+        }
+    }
+
+    // Tasks from older files have no ID yet, and hand-edited files may
+    // repeat one; give those a fresh ID (`load_tasks` saves it right away).
+    let mut seen = HashSet::new();
+    let mut assigned_ids = false;
+    for i in 0..tasks.len() {
+        if tasks[i].id.is_empty() || !seen.insert(tasks[i].id.clone()) {
+            tasks[i].id = new_task_id(&tasks);
+            seen.insert(tasks[i].id.clone());
+            assigned_ids = true;
+        }
+    }
+    Ok(ReadTasks {
+        tasks,
+        rejected,
+        version,
+        assigned_ids,
+    })
+}
+
+/// Appends rejected lines to tasks.rejected, each after a comment saying when
+/// and why it was rejected, so nothing is lost and the lines can be fixed by
+/// hand and pasted back.
+fn quarantine_lines(rejected_path: &Path, rejected: &[RejectedLine]) -> io::Result<()> {
+    let mut f = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(rejected_path)?;
+    let now = Local::now().format("%Y-%m-%d %H:%M");
+    for line in rejected {
+        writeln!(f, "# {now} line {}: {}", line.number, line.reason)?;
+        f.write_all(&line.raw)?;
+        f.write_all(b"\n")?;
+    }
+    f.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::tests::{scratch_dir, task};
+
+    #[test]
+    fn unreadable_lines_are_moved_aside() {
+        let dir = scratch_dir("quarantine");
+        let path = dir.join("todo.tsv");
+        let good = format_task_line(&task("abcdef", "keep me"));
+        let content =
+            format!("{TASKS_HEADER}{FORMAT_VERSION}\n{good}\n0\tinbox\tyesterday\tlost?\n");
+        fs::write(&path, content).unwrap();
+
+        let mut store = TsvStore::new(path.clone());
+        let tasks = store.load().unwrap();
+        assert_eq!(tasks, [task("abcdef", "keep me")]);
+        let warnings = store.take_warnings();
+        assert_eq!(warnings[0], "⚠ 1 unreadable line moved to todo.rejected:");
+        assert_eq!(warnings[1], "  line 3: bad creation time 'yesterday'");
+
+        let rejected = fs::read_to_string(dir.join("todo.rejected")).unwrap();
+        let mut lines = rejected.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .ends_with("line 3: bad creation time 'yesterday'"));
+        assert_eq!(lines.next(), Some("0\tinbox\tyesterday\tlost?"));
+        // The list itself no longer has the line.
+        assert!(!fs::read_to_string(&path).unwrap().contains("lost?"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_failed_backup_does_not_stop_the_save() {
+        let dir = scratch_dir("backup-failed");
+        let path = dir.join("tasks.tsv");
+        let mut store = TsvStore::new(path.clone());
+        store.replace(&[task("abcdef", "first")]).unwrap();
+        // A file where the backups directory should be.
+        fs::write(dir.join("tasks.backups"), "").unwrap();
+
+        store.replace(&[task("abcdef", "second")]).unwrap();
+        assert_eq!(
+            TsvStore::new(path).load().unwrap(),
+            [task("abcdef", "second")]
+        );
+        let warnings = store.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("⚠ Could not back up"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn lists_in_one_directory_keep_separate_backups() {
+        let dir = scratch_dir("backup-per-list");
+        for name in ["todo.tsv", "work.tsv"] {
+            let mut store = TsvStore::new(dir.join(name));
+            store.replace(&[task("abcdef", "first")]).unwrap();
+            store.replace(&[task("abcdef", "second")]).unwrap();
+        }
+        for stem in ["todo", "work"] {
+            let copies: Vec<String> = fs::read_dir(dir.join(format!("{stem}.backups")))
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            assert_eq!(copies.len(), 1);
+            assert!(copies[0].starts_with(&format!("{stem}-")));
+        }
+        assert!(!dir.join("backups").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn nothing_to_apply_leaves_the_file_alone() {
+        let dir = scratch_dir("apply-nothing");
+        let path = dir.join("tasks.tsv");
+        fs::write(&path, format!("{TASKS_HEADER}{FORMAT_VERSION}\n")).unwrap();
+        let mut store = TsvStore::new(path);
+        store.load().unwrap();
+        store.apply(&[]).unwrap();
+        assert!(!dir.join("tasks.backups").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unversioned_files_are_upgraded_and_kept() {
+        let dir = scratch_dir("upgrade");
+        let path = dir.join("tasks.tsv");
+        let original = "0\twork\t2026-10-17T12:15:58+02:00\tcall back\tabout the quote\n";
+        fs::write(&path, original).unwrap();
+
+        let mut store = TsvStore::new(path.clone());
+        let tasks = store.load().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].text, "call back\tabout the quote");
+        assert_eq!(tasks[0].id.len(), crate::task::ID_LENGTH);
+        assert_eq!(
+            fs::read_to_string(dir.join("tasks.v1.tsv")).unwrap(),
+            original
+        );
+
+        let upgraded = fs::read_to_string(&path).unwrap();
+        assert!(upgraded.starts_with(&format!("{TASKS_HEADER}{FORMAT_VERSION}\n")));
+        assert_eq!(TsvStore::new(path).load().unwrap(), tasks);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reading_changes_nothing() {
+        let dir = scratch_dir("read-only");
+        let path = dir.join("tasks.tsv");
+        let original = "0\tinbox\t2026-10-17T12:15:58+02:00\tcheck\n0\tinbox\n";
+        fs::write(&path, original).unwrap();
+
+        let read = read_tasks(&path).unwrap();
+        assert_eq!(read.tasks.len(), 1);
+        assert_eq!(read.rejected.len(), 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
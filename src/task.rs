@@ -0,0 +1,314 @@
+//! Tasks and what they carry: IDs, tags, priorities and due dates.
+
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDate, Weekday};
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::BuildHasher;
+
+/// One entry of the list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task {
+    /// Stable identifier from `new_task_id`; unlike the task number it never
+    /// changes when other tasks are added, deleted or re-sorted.
+    pub id: String,
+    /// What to do, without the `due:` and `pri:` tokens it was typed with.
+    pub text: String,
+    /// Whether the task is done; change it with `set_done` to keep
+    /// `completed_at` in step.
+    pub done: bool,
+    /// The folder the task is filed in.
+    pub folder: String,
+    /// When the task was added.
+    pub created_at: DateTime<Local>,
+    /// When the task was last marked done; None while it is open.
+    pub completed_at: Option<DateTime<Local>>,
+    /// The day the task is due, if any.
+    pub due: Option<NaiveDate>,
+    /// How important the task is, if set.
+    pub priority: Option<Priority>,
+    /// `#tag` and `+project` tokens found in the text, sigil included.
+    pub tags: Vec<String>,
+}
+
+/// A task's priority. Declared from most to least important so that
+/// sorting puts High first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// `pri:high`, shown as H.
+    High,
+    /// `pri:medium`, shown as M.
+    Medium,
+    /// `pri:low`, shown as L.
+    Low,
+}
+
+// IDs use letters only, without look-alikes (i, l, o), so one can never be
+// mistaken for a task number wherever either is accepted.
+const ID_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz";
+
+/// Number of letters in a task ID.
+pub const ID_LENGTH: usize = 6;
+
+/// A task as the user names it: its number in the current view, or its ID.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskRef {
+    /// 1-based position in a `View`.
+    Number(usize),
+    /// The task's `id`, in lowercase.
+    Id(String),
+}
+
+impl fmt::Display for TaskRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TaskRef::Number(number) => write!(f, "{number}"),
+            TaskRef::Id(id) => write!(f, "{id}"),
+        }
+    }
+}
+
+/// Reads a task number or ID as typed by the user; None for anything else.
+pub fn parse_task_ref(value: &str) -> Option<TaskRef> {
+    if let Ok(number) = value.parse::<usize>() {
+        return (number > 0).then_some(TaskRef::Number(number));
+    }
+    let id = value.to_ascii_lowercase();
+    let valid = id.len() == ID_LENGTH && id.bytes().all(|b| ID_ALPHABET.contains(&b));
+    valid.then_some(TaskRef::Id(id))
+}
+
+/// A random ID not used by any task yet.
+pub fn new_task_id(tasks: &[Task]) -> String {
+    let state = RandomState::new();
+    let mut attempt = 0u64;
+    loop {
+        let mut hash = state.hash_one((Local::now().timestamp_nanos_opt(), attempt));
+        let id: String = (0..ID_LENGTH)
+            .map(|_| {
+                let letter = ID_ALPHABET[(hash % ID_ALPHABET.len() as u64) as usize];
+                hash /= ID_ALPHABET.len() as u64;
+                letter as char
+            })
+            .collect();
+        if !tasks.iter().any(|t| t.id == id) {
+            return id;
+        }
+        attempt += 1;
+    }
+}
+
+/// A tag is a word starting with `#` or `+`; trailing punctuation such as
+/// the comma in "#review," is not part of it.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        let mut chars = word.chars();
+        let Some(sigil @ ('#' | '+')) = chars.next() else {
+            continue;
+        };
+        let name: String = chars
+            .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'))
+            .collect();
+        if name.is_empty() {
+            continue;
+        }
+        let tag = format!("{sigil}{}", name.to_lowercase());
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// `+urgent` and `#urgent` only match their own kind; a bare `urgent`
+/// matches either.
+pub fn has_tag(task: &Task, filter: &str) -> bool {
+    let filter = filter.trim().to_lowercase();
+    task.tags.iter().any(|tag| {
+        if filter.starts_with(['#', '+']) {
+            *tag == filter
+        } else {
+            tag[1..] == filter
+        }
+    })
+}
+
+/// Completing a task stamps the time; reopening it clears the stamp.
+pub fn set_done(task: &mut Task, done: bool) {
+    if task.done != done {
+        task.done = done;
+        task.completed_at = if done { Some(Local::now()) } else { None };
+    }
+}
+
+/// Reads a priority level: high, medium or low, their first letters, or
+/// a, b and c.
+pub fn parse_priority(value: &str) -> Option<Priority> {
+    match value.trim().to_lowercase().as_str() {
+        "h" | "high" | "a" => Some(Priority::High),
+        "m" | "medium" | "med" | "b" => Some(Priority::Medium),
+        "l" | "low" | "c" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// Parses the level part of `priority <number> <level>`; "none" clears it.
+pub fn parse_priority_setting(value: &str) -> Option<Option<Priority>> {
+    if value.trim().eq_ignore_ascii_case("none") {
+        return Some(None);
+    }
+    parse_priority(value).map(Some)
+}
+
+/// The level as it is written in task files and shown to the user.
+pub fn priority_name(priority: Priority) -> &'static str {
+    match priority {
+        Priority::High => "high",
+        Priority::Medium => "medium",
+        Priority::Low => "low",
+    }
+}
+
+/// Removes `pri:<level>` tokens from task text, like `extract_due`.
+pub fn extract_priority(text: &str) -> (String, Option<Priority>) {
+    let mut priority = None;
+    let mut words = Vec::new();
+    for word in text.split_whitespace() {
+        match word.strip_prefix("pri:").and_then(parse_priority) {
+            Some(level) => priority = Some(level),
+            None => words.push(word),
+        }
+    }
+    (words.join(" "), priority)
+}
+
+/// One level up; no priority becomes low and high stays high.
+pub fn raise_priority(priority: Option<Priority>) -> Option<Priority> {
+    match priority {
+        None => Some(Priority::Low),
+        Some(Priority::Low) => Some(Priority::Medium),
+        Some(Priority::Medium | Priority::High) => Some(Priority::High),
+    }
+}
+
+/// One level down; low becomes no priority.
+pub fn lower_priority(priority: Option<Priority>) -> Option<Priority> {
+    match priority {
+        Some(Priority::High) => Some(Priority::Medium),
+        Some(Priority::Medium) => Some(Priority::Low),
+        Some(Priority::Low) | None => None,
+    }
+}
+
+/// Accepts ISO dates (2026-11-01), "today", "tomorrow", weekday names
+/// (the next such day, today included) and offsets like "3d" or "2w".
+pub fn parse_due(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let value = value.trim().to_lowercase();
+    if let Ok(date) = NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+        return Some(date);
+    }
+    match value.as_str() {
+        "today" | "tod" => return Some(today),
+        "tomorrow" | "tom" => return Some(today + ChronoDuration::days(1)),
+        _ => {}
+    }
+    if let Ok(weekday) = value.parse::<Weekday>() {
+        let ahead =
+            (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+        return Some(today + ChronoDuration::days(ahead as i64));
+    }
+    let days = if let Some(amount) = value.strip_suffix('d') {
+        amount.parse::<i64>().ok()?
+    } else if let Some(amount) = value.strip_suffix('w') {
+        amount.parse::<i64>().ok()?.checked_mul(7)?
+    } else {
+        return None;
+    };
+    today.checked_add_signed(ChronoDuration::try_days(days)?)
+}
+
+/// Parses the date part of `due <number> <date>`; "none" clears the due date.
+pub fn parse_due_setting(value: &str) -> Option<Option<NaiveDate>> {
+    if value.trim().eq_ignore_ascii_case("none") {
+        return Some(None);
+    }
+    parse_due(value, Local::now().date_naive()).map(Some)
+}
+
+/// Removes `due:<date>` tokens from task text. Tokens whose date cannot be
+/// understood are left in the text so nothing the user typed disappears.
+pub fn extract_due(text: &str, today: NaiveDate) -> (String, Option<NaiveDate>) {
+    let mut due = None;
+    let mut words = Vec::new();
+    for word in text.split_whitespace() {
+        match word.strip_prefix("due:").and_then(|v| parse_due(v, today)) {
+            Some(date) => due = Some(date),
+            None => words.push(word),
+        }
+    }
+    (words.join(" "), due)
+}
+
+/// What is left of task text once `extract_due` and `extract_priority` have
+/// taken their tokens out. A task needs some of it: `due:friday pri:high`
+/// alone says when and how important, but not what.
+pub fn task_text(text: &str, today: NaiveDate) -> String {
+    let (text, _) = extract_due(text, today);
+    extract_priority(&text).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn due_offsets_and_names() {
+        // A Saturday.
+        let today = day("2026-10-17");
+        assert_eq!(parse_due("2026-11-01", today), Some(day("2026-11-01")));
+        assert_eq!(parse_due("tomorrow", today), Some(day("2026-10-18")));
+        assert_eq!(parse_due("3d", today), Some(day("2026-10-20")));
+        assert_eq!(parse_due("2w", today), Some(day("2026-10-31")));
+        assert_eq!(parse_due("monday", today), Some(day("2026-10-19")));
+        assert_eq!(parse_due("saturday", today), Some(day("2026-10-17")));
+    }
+
+    #[test]
+    fn due_rejects_what_it_cannot_read() {
+        let today = day("2026-10-17");
+        for value in [
+            "",
+            "d",
+            "w",
+            "soon",
+            "3x",
+            "é",
+            "3日",
+            "日d",
+            "9999999999999999999w",
+        ] {
+            assert_eq!(parse_due(value, today), None, "{value:?}");
+        }
+        assert_eq!(parse_due("1000000000000000000w", today), None);
+    }
+
+    #[test]
+    fn tokens_alone_leave_no_task_text() {
+        let today = day("2026-10-17");
+        assert_eq!(task_text("due:tomorrow pri:high", today), "");
+        assert_eq!(task_text("pay rent due:friday", today), "pay rent");
+        assert_eq!(task_text("due:someday", today), "due:someday");
+    }
+
+    #[test]
+    fn unreadable_due_stays_in_the_text() {
+        let today = day("2026-10-17");
+        let (text, due) = extract_due("buy milk due:é due:3日", today);
+        assert_eq!(text, "buy milk due:é due:3日");
+        assert_eq!(due, None);
+    }
+}
@@ -0,0 +1,104 @@
+//! Lines submitted at the command prompt, oldest first, so Up and Down can
+//! bring them back. They are kept next to the list, one per line, in a file
+//! named after it (tasks.commands.txt for tasks.tsv), and so survive
+//! restarts.
+
+use super::LineEditor;
+use crate::store::companion_path;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Most lines kept; older ones are dropped.
+const LIMIT: usize = 200;
+
+/// The command lines typed so far, and how far back Up has gone in them.
+#[derive(Default)]
+pub struct CommandHistory {
+    entries: Vec<String>,
+    // None keeps the history in memory only.
+    path: Option<PathBuf>,
+    // The entry on the prompt while browsing, and what was typed before the
+    // first Up; Down past the newest entry puts that back.
+    position: Option<usize>,
+    draft: LineEditor,
+}
+
+impl CommandHistory {
+    /// The history kept next to the list stored at `store_path`; empty if
+    /// there is none yet or it cannot be read.
+    pub fn open(store_path: &Path) -> Self {
+        let path = companion_path(store_path, ".commands.txt");
+        let mut entries: Vec<String> = fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::to_string)
+            .collect();
+        entries.drain(..entries.len().saturating_sub(LIMIT));
+        CommandHistory {
+            entries,
+            path: Some(path),
+            ..CommandHistory::default()
+        }
+    }
+
+    /// Switches to the history next to another list, as `profile` does. A
+    /// history kept in memory only stays as it is.
+    pub fn reopen(&mut self, store_path: &Path) {
+        if self.path.is_some() {
+            *self = CommandHistory::open(store_path);
+        }
+    }
+
+    /// Adds a submitted line and saves the history. Blank lines and repeats
+    /// of the newest entry are left out.
+    pub fn record(&mut self, line: &str) {
+        self.position = None;
+        let line = line.trim();
+        if line.is_empty() || self.entries.last().is_some_and(|last| last == line) {
+            return;
+        }
+        self.entries.push(line.to_string());
+        self.entries
+            .drain(..self.entries.len().saturating_sub(LIMIT));
+        if let Some(path) = &self.path {
+            // Best effort: a lost history is not worth an error on screen.
+            let _ = fs::write(path, self.entries.join("\n") + "\n");
+        }
+    }
+
+    /// Up: puts the entry before the one shown on the prompt, starting with
+    /// the newest. Stays on the oldest.
+    pub fn older(&mut self, buffer: &mut LineEditor) {
+        let position = match self.position {
+            None if self.entries.is_empty() => return,
+            None => {
+                self.draft = buffer.clone();
+                self.entries.len() - 1
+            }
+            Some(position) => position.saturating_sub(1),
+        };
+        self.position = Some(position);
+        *buffer = LineEditor::from(self.entries[position].as_str());
+    }
+
+    /// Down: puts the entry after the one shown on the prompt, and after the
+    /// newest what was being typed before Up was first pressed.
+    pub fn newer(&mut self, buffer: &mut LineEditor) {
+        let Some(position) = self.position else {
+            return;
+        };
+        if position + 1 < self.entries.len() {
+            self.position = Some(position + 1);
+            *buffer = LineEditor::from(self.entries[position + 1].as_str());
+        } else {
+            self.position = None;
+            *buffer = std::mem::take(&mut self.draft);
+        }
+    }
+
+    /// Stops browsing. The next Up starts again from the newest entry.
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+}
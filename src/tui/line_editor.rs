@@ -0,0 +1,166 @@
+//! The text typed at a prompt and the cursor inside it. The command prompt
+//! and every prompt a command opens edit their line with the same keys:
+//! Left/Right/Home/End move, Backspace and Delete remove a character, Ctrl-W
+//! the word before the cursor and Ctrl-U the whole line.
+
+use super::{CURSOR, RESET};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+// Stands in for text scrolled out of view at either end of the prompt.
+const MORE: char = '…';
+
+/// One line of input and a cursor, counted in characters from the start.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct LineEditor {
+    text: String,
+    cursor: usize,
+}
+
+impl LineEditor {
+    /// The text typed so far.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Where the cursor is, from 0 (before the first character) to the
+    /// length of the text (after the last).
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// True when nothing has been typed.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Empties the line.
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    // The byte offset of the character at `pos`, or the end of the text.
+    fn offset(&self, pos: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(pos)
+            .map_or(self.text.len(), |(offset, _)| offset)
+    }
+
+    /// Inserts `text` at the cursor and moves the cursor past it, as a paste
+    /// does. Line breaks and tabs become spaces, since a prompt is one line.
+    pub fn insert_str(&mut self, text: &str) {
+        let text: String = text
+            .chars()
+            .filter(|&ch| ch != '\r')
+            .map(|ch| if ch.is_control() { ' ' } else { ch })
+            .collect();
+        let offset = self.offset(self.cursor);
+        self.text.insert_str(offset, &text);
+        self.cursor += text.chars().count();
+    }
+
+    /// Applies an editing key. Returns false for keys that do not edit, which
+    /// the prompt handles itself (Enter, Esc, Ctrl-E and so on).
+    pub fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.len(),
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.text.remove(self.offset(self.cursor));
+            }
+            KeyCode::Delete if self.cursor < self.len() => {
+                self.text.remove(self.offset(self.cursor));
+            }
+            KeyCode::Backspace | KeyCode::Delete => {}
+            KeyCode::Char('w') if ctrl => {
+                let before: Vec<char> = self.text.chars().take(self.cursor).collect();
+                let mut start = self.cursor;
+                while start > 0 && before[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+                while start > 0 && !before[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+                let range = self.offset(start)..self.offset(self.cursor);
+                self.text.replace_range(range, "");
+                self.cursor = start;
+            }
+            KeyCode::Char('u') if ctrl => self.clear(),
+            KeyCode::Char(c) if !ctrl => {
+                self.text.insert(self.offset(self.cursor), c);
+                self.cursor += 1;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// The line as drawn in `width` columns, with the cursor highlighted.
+    /// Text that does not fit scrolls sideways to keep the cursor in view,
+    /// and a `…` marks each end that has more text beyond it.
+    pub(crate) fn render(&self, width: usize) -> String {
+        let len = self.len();
+        // The cursor needs a cell of its own when it is after the text.
+        let cells = len + 1;
+        let (start, more_before, more_after) = if cells <= width {
+            (0, false, false)
+        } else if width < 3 {
+            ((self.cursor + 1).saturating_sub(width), false, false)
+        } else if self.cursor + 2 <= width {
+            (0, false, true)
+        } else {
+            // Cursor at the right edge, between the two markers, unless the
+            // end of the text is in view and the right marker is not needed.
+            let start = self.cursor + 3 - width;
+            if start + width - 2 >= len {
+                let end = if self.cursor == len { cells } else { len };
+                (end + 1 - width, true, false)
+            } else {
+                (start, true, true)
+            }
+        };
+        let columns = width - more_before as usize - more_after as usize;
+
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut out = String::new();
+        if more_before {
+            out.push(MORE);
+        }
+        for i in start..(start + columns).min(cells) {
+            let ch = chars.get(i).copied().unwrap_or(' ');
+            if i == self.cursor {
+                out.push_str(&format!("{CURSOR}{ch}{RESET}"));
+            } else {
+                out.push(ch);
+            }
+        }
+        if more_after {
+            out.push(MORE);
+        }
+        out
+    }
+}
+
+impl From<String> for LineEditor {
+    /// A line holding `text` with the cursor at its end.
+    fn from(text: String) -> Self {
+        let cursor = text.chars().count();
+        LineEditor { text, cursor }
+    }
+}
+
+impl From<&str> for LineEditor {
+    /// A line holding `text` with the cursor at its end.
+    fn from(text: &str) -> Self {
+        LineEditor::from(text.to_string())
+    }
+}
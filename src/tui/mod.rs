@@ -0,0 +1,1654 @@
+//! The interactive TUI without a terminal: its state, `handle_key`, which
+//! applies a key press to it, and `render`, which draws it into a `Frame`.
+//! The binary feeds it terminal events; tests feed it keys and compare the
+//! frames with snapshots.
+
+// PASTEL TODO — Responsive Layout (Tab Header ×2, Tab Body ×1)
+// -------------------------------------------------------------
+// The table fills the terminal: columns and visible rows follow its size
+// and are recomputed on resize. Narrow panes drop the date columns.
+// Header uses double tab (\t\t), body uses single tab (\t).
+// If terminal smaller than MIN_WIDTH×MIN_HEIGHT → ask for a bigger one.
+// -------------------------------------------------------------
+
+use crate::{
+    apply_action, first_folder, folder_indices, folder_names, folder_task_index, lock_for_write,
+    lower_priority, merge_tasks, new_task_id, parse_due_setting, parse_task_ref, profile_names,
+    raise_priority, set_done, task_text, try_open_store, valid_profile_name, CommandAction,
+    Priority, SortOrder, Storage, Task, TaskRef, View, ID_LENGTH,
+};
+use chrono::{Local, NaiveDate};
+use crossterm::cursor::{Hide, Show};
+use crossterm::event::{
+    DisableBracketedPaste, EnableBracketedPaste, KeyCode, KeyEvent, KeyModifiers,
+};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+mod command_history;
+mod line_editor;
+
+pub use command_history::CommandHistory;
+pub use line_editor::LineEditor;
+
+/// Ends any styling started with the colors below.
+pub const RESET: &str = "\x1b[0m";
+/// The pink used for labels, and for the goodbye line.
+pub const ACCENT: &str = "\x1b[38;5;219m";
+const TODO_COLOR: &str = "\x1b[38;5;153m";
+const DONE_COLOR: &str = "\x1b[38;5;151m";
+const DATE_COLOR: &str = "\x1b[38;5;223m";
+const FOLDER_COLOR: &str = "\x1b[38;5;212m";
+const TIP_TEXT: &str = "\x1b[38;5;251m";
+const HEADER_BG_INVERT: &str = "\x1b[48;5;60m";
+const HEADER_FG_INVERT: &str = "\x1b[38;5;218m";
+const BOLD: &str = "\x1b[1m";
+const ITALIC: &str = "\x1b[3m";
+const DIM: &str = "\x1b[2m";
+const VALUE_COLOR: &str = "\x1b[38;5;159m";
+const SUMMARY_COLOR: &str = "\x1b[38;5;183m";
+const POINTER_COLOR: &str = "\x1b[38;5;218m";
+const BORDER_COLOR: &str = "\x1b[38;5;225m";
+const OVERDUE_COLOR: &str = "\x1b[38;5;210m";
+const DUE_TODAY_COLOR: &str = "\x1b[38;5;216m";
+const DUE_SOON_COLOR: &str = "\x1b[38;5;229m";
+const PRIORITY_HIGH_COLOR: &str = "\x1b[38;5;211m";
+const PRIORITY_MEDIUM_COLOR: &str = "\x1b[38;5;222m";
+const PRIORITY_LOW_COLOR: &str = "\x1b[38;5;117m";
+const ERROR_COLOR: &str = "\x1b[38;5;203m";
+// Reverse video marks the prompt cursor; the terminal's own cursor stays hidden.
+const CURSOR: &str = "\x1b[7m";
+
+// Widest the table grows on large monitors (inner width, borders excluded).
+const MAX_TABLE_WIDTH: usize = 120;
+// Narrowest the task text column may get before a date column is dropped.
+const MIN_TASK_COLUMN_WIDTH: usize = 20;
+// Lines used by everything except the task rows, plus one spare line so the
+// final newline never scrolls the frame.
+const FRAME_LINES: u16 = 17;
+// How often the tip line rotates, in seconds.
+const TIP_SECONDS: i64 = 15;
+// Tasks due within this many days are highlighted as "due soon".
+const DUE_SOON_DAYS: i64 = 3;
+
+// 👉 Layout size configuration
+// -------------------------------------------------------------
+// Minimum terminal size (change these numbers if needed)
+const MIN_WIDTH: u16 = 32;
+// Minimum height (change if you want taller layout)
+const MIN_HEIGHT: u16 = 20;
+// -------------------------------------------------------------
+
+/// Messages shown around the table: the last save failure (until a save
+/// works again), what could not be loaded and the answer to the last key
+/// (both until the next key press).
+#[derive(Default)]
+pub struct Status {
+    /// Why the last save failed; cleared by the next one that works.
+    pub save_error: Option<String>,
+    /// Warnings for the banner above the table, one line each.
+    pub load_warnings: Vec<String>,
+    /// What the last key had to say, such as why a command did nothing or
+    /// which names Tab could complete; shown below the load warnings, one
+    /// line each.
+    pub message: Vec<String>,
+    /// Another TUI has the list open; changes are refused until it exits.
+    pub read_only: bool,
+}
+
+const NO_TEXT_MESSAGE: &str = "⚠ A task needs some text besides due: and pri:.";
+
+const READ_ONLY_WARNING: &str =
+    "⚠ Read-only: another instance has this list open, so changes are not saved.";
+
+/// Sizes derived from the terminal by `layout_for`.
+pub struct Layout {
+    cols: u16,
+    lines: u16,
+    width: usize,
+    task_width: usize,
+    rows: usize,
+    show_created: bool,
+    show_due: bool,
+}
+
+/// What keys do right now.
+pub enum Mode {
+    /// Typing a command word at the command prompt.
+    Command,
+    /// Answering the prompt a command opened.
+    CommandInput(CommandContext),
+    /// Moving through the table.
+    Navigate {
+        /// The row of the view the pointer is on.
+        selected: usize,
+    },
+}
+
+/// The prompt a command opened, with what has been typed into it.
+#[allow(missing_docs)]
+pub enum CommandContext {
+    /// `add`: the task text.
+    Add { buffer: LineEditor },
+    /// `folder`: the folder to switch to.
+    Folder { buffer: LineEditor },
+    /// `delete`: a task number or ID, or `folder NAME`.
+    Delete { buffer: LineEditor },
+    /// `due`: a task and a date.
+    Due { buffer: LineEditor },
+    /// `filter`: a tag, or nothing to clear the filter.
+    Filter { buffer: LineEditor },
+    /// `profile`: a profile name. `names` are the existing profiles, read
+    /// once when the prompt opens and offered while it is empty.
+    Profile {
+        buffer: LineEditor,
+        names: Vec<String>,
+    },
+    /// `edit`: asks for a task number first, then holds that task's text
+    /// for editing. `task` is the task as it was when the prompt opened: it
+    /// is found again by ID, so changes merged in from elsewhere cannot
+    /// retarget the edit, and its text shows whether they changed it too.
+    Edit {
+        task: Option<Task>,
+        buffer: LineEditor,
+    },
+}
+
+impl CommandContext {
+    fn buffer_mut(&mut self) -> &mut LineEditor {
+        match self {
+            CommandContext::Add { buffer }
+            | CommandContext::Folder { buffer }
+            | CommandContext::Delete { buffer }
+            | CommandContext::Due { buffer }
+            | CommandContext::Filter { buffer }
+            | CommandContext::Profile { buffer, .. }
+            | CommandContext::Edit { buffer, .. } => buffer,
+        }
+    }
+}
+
+/// Owns the terminal while the TUI runs: raw mode, the alternate screen and a
+/// hidden cursor. Dropping it (or panicking) puts the shell back as it was.
+pub struct TerminalGuard;
+impl TerminalGuard {
+    /// Takes over the terminal.
+    pub fn new() -> io::Result<Self> {
+        install_panic_hook();
+        setup_terminal()?;
+        Ok(Self)
+    }
+}
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+// Set while the TUI owns the terminal. Restoring twice (panic hook, then the
+// guard while unwinding) would jump the cursor back over the panic message.
+static TERMINAL_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+fn setup_terminal() -> io::Result<()> {
+    enable_raw_mode()?;
+    TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
+    execute!(
+        io::stdout(),
+        EnterAlternateScreen,
+        Hide,
+        EnableBracketedPaste
+    )
+}
+
+fn restore_terminal() {
+    if TERMINAL_ACTIVE.swap(false, Ordering::SeqCst) {
+        let _ = execute!(
+            io::stdout(),
+            DisableBracketedPaste,
+            Show,
+            LeaveAlternateScreen
+        );
+        let _ = disable_raw_mode();
+    }
+}
+
+// Restore the terminal before the panic message is printed, otherwise it
+// lands on the alternate screen in raw mode and the shell is left unusable.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default_hook(info);
+    }));
+}
+
+// ---- Layout ----
+/// The layout for a `cols` × `lines` terminal.
+///
+/// Row layout: "› 12.  ○▲ task text… 17/10/26 18/10/26 ". The fixed part in
+/// front of the text is 10 columns and each date column takes 9. The created
+/// date is dropped first when space runs out, then the due date.
+pub fn layout_for(cols: u16, lines: u16) -> Layout {
+    let width = (cols as usize).saturating_sub(2).min(MAX_TABLE_WIDTH);
+    let text_space = width.saturating_sub(10 + 1);
+    let (show_created, show_due) = if text_space >= MIN_TASK_COLUMN_WIDTH + 18 {
+        (true, true)
+    } else if text_space >= MIN_TASK_COLUMN_WIDTH + 9 {
+        (false, true)
+    } else {
+        (false, false)
+    };
+    let date_columns = 9 * (show_created as usize + show_due as usize);
+    Layout {
+        cols,
+        lines,
+        width,
+        task_width: text_space - date_columns,
+        rows: lines.saturating_sub(FRAME_LINES).max(1) as usize,
+        show_created,
+        show_due,
+    }
+}
+
+// ---- Render ----
+/// One screenful, one string per terminal line, styled with ANSI color
+/// codes. `Screen::draw` puts it on a terminal; `text` gives it without the
+/// styling, as snapshots compare it.
+pub struct Frame {
+    lines: Vec<String>,
+}
+
+impl Frame {
+    /// The lines with their escape codes.
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// The lines without escape codes, each ending in a newline.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for line in &self.lines {
+            text.extend(parse_cells(line).iter().map(|cell| cell.ch));
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Draws the TUI into a frame. `cmd` is what has been typed at the command
+/// prompt and `tick` picks the tip shown under the table (see `tip_tick`).
+pub fn render(
+    tasks: &[Task],
+    view: &View,
+    mode: &Mode,
+    cmd: &LineEditor,
+    layout: &Layout,
+    status: &Status,
+    tick: u64,
+) -> Frame {
+    let mut frame = Vec::new();
+
+    if layout.cols < MIN_WIDTH || layout.lines < MIN_HEIGHT {
+        frame.push(format!("{ACCENT}Terminal too small{RESET}"));
+        frame.push(format!(
+            "{DIM}need {}x{}, have {}x{}{RESET}",
+            MIN_WIDTH, MIN_HEIGHT, layout.cols, layout.lines
+        ));
+        return Frame { lines: frame };
+    }
+
+    // Load warnings and messages take rows from the task list, leaving at
+    // least one.
+    let warnings = status.load_warnings.len();
+    let mut banner: Vec<String> = status
+        .load_warnings
+        .iter()
+        .chain(&status.message)
+        .cloned()
+        .collect();
+    let room = layout.rows.saturating_sub(2);
+    if banner.len() > room {
+        // Keep the heading when there is only room for one line.
+        let keep = if room > 1 { room - 1 } else { room };
+        let hidden = banner.len() - keep;
+        banner.truncate(keep);
+        if room > 1 {
+            banner.push(format!("  …and {hidden} more"));
+        }
+    }
+    let rows = if banner.is_empty() {
+        layout.rows
+    } else {
+        layout.rows - banner.len() - 1
+    };
+
+    let folder = view.folder.as_str();
+    let folder_indices = folder_indices(tasks, view);
+    let folder_count = folder_indices.len();
+    let selected_idx = match mode {
+        Mode::Navigate { selected } if folder_count > 0 => Some((*selected).min(folder_count - 1)),
+        _ => None,
+    };
+    let mut start = 0usize;
+    if let Some(sel) = selected_idx {
+        if sel + 1 > rows {
+            start = sel + 1 - rows;
+        }
+        if folder_count > rows && start + rows > folder_count {
+            start = folder_count - rows;
+        }
+    } else if folder_count > rows {
+        start = folder_count - rows;
+    }
+
+    let horizontal = "─".repeat(layout.width);
+    frame.push(format!(
+        "{border}╭{line}╮{reset}",
+        border = BORDER_COLOR,
+        line = &horizontal,
+        reset = RESET
+    ));
+    let read_only = if status.read_only {
+        format!("  {ERROR_COLOR}read-only: open in another instance{RESET}")
+    } else {
+        String::new()
+    };
+    frame.push(table_row(
+        &format!("{HEADER_BG_INVERT}{HEADER_FG_INVERT}{BOLD} PASTEL TODO {RESET}{read_only}"),
+        layout.width,
+    ));
+    frame.push(format!(
+        "{border}├{line}┤{reset}",
+        border = BORDER_COLOR,
+        line = &horizontal,
+        reset = RESET
+    ));
+    for (i, line) in banner.iter().enumerate() {
+        let (color, hint) = match i {
+            0 if warnings > 0 => (ERROR_COLOR, format!(" {DIM}(any key dismisses){RESET}")),
+            i if i < warnings => (ERROR_COLOR, String::new()),
+            _ => (ACCENT, String::new()),
+        };
+        frame.push(table_row(
+            &format!("{color}{line}{RESET}{hint}"),
+            layout.width,
+        ));
+    }
+    if !banner.is_empty() {
+        frame.push(format!(
+            "{border}├{line}┤{reset}",
+            border = BORDER_COLOR,
+            line = &horizontal,
+            reset = RESET
+        ));
+    }
+
+    frame.push(table_row(&format!(
+        "{label}Total:{reset} {value}{total:<3}{reset}  {label}Folder:{reset} {folder_color}{folder}{reset} ({value}{count}{reset})",
+        label = ACCENT,
+        reset = RESET,
+        value = VALUE_COLOR,
+        total = tasks.len(),
+        folder_color = FOLDER_COLOR,
+        folder = folder,
+        count = folder_count
+    ), layout.width));
+    let sort_name = match view.sort {
+        SortOrder::Added => "added",
+        SortOrder::Priority => "priority",
+    };
+    let filter_info = match &view.filter {
+        Some(tag) => format!("  {ACCENT}Filter:{RESET} {VALUE_COLOR}{tag}{RESET}"),
+        None => String::new(),
+    };
+    frame.push(table_row(&format!(
+        "{label}Folder Name:{reset} {folder_color}{folder}{reset}  {label}Profile:{reset} {value}{profile}{reset}  {label}Sort:{reset} {value}{sort}{reset}{filter}",
+        label = ACCENT,
+        reset = RESET,
+        folder_color = FOLDER_COLOR,
+        folder = folder,
+        value = VALUE_COLOR,
+        profile = view.profile.as_deref().unwrap_or("default"),
+        sort = sort_name,
+        filter = filter_info
+    ), layout.width));
+    frame.push(format!(
+        "{border}├{line}┤{reset}",
+        border = BORDER_COLOR,
+        line = &horizontal,
+        reset = RESET
+    ));
+
+    // The ID column (toggled with the `ids` command) comes out of the task text.
+    let id_width = if view.show_ids { ID_LENGTH + 1 } else { 0 };
+    let task_width = layout.task_width.saturating_sub(id_width);
+    let id_header = if view.show_ids { "ID     " } else { "" };
+    let created_header = if layout.show_created { " Created " } else { "" };
+    let due_header = if layout.show_due { " Due" } else { "" };
+    frame.push(table_row(
+        &format!(
+            "{label}  No.  ○! {ids}{task:<width$}{created}{due}{reset}",
+            label = ACCENT,
+            reset = RESET,
+            ids = id_header,
+            task = "Task",
+            width = task_width,
+            created = created_header,
+            due = due_header
+        ),
+        layout.width,
+    ));
+    frame.push(format!(
+        "{border}├{line}┤{reset}",
+        border = BORDER_COLOR,
+        line = &horizontal,
+        reset = RESET
+    ));
+
+    let visible_items: Vec<(usize, &Task)> = folder_indices
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(rows)
+        .map(|(order, idx)| (order, &tasks[*idx]))
+        .collect();
+
+    let today = Local::now().date_naive();
+    for (order, task) in &visible_items {
+        let is_selected = selected_idx == Some(*order);
+        let pointer = if is_selected {
+            format!("{POINTER_COLOR}›{RESET}")
+        } else {
+            format!("{BORDER_COLOR}•{RESET}")
+        };
+        let status = if task.done {
+            format!("{DONE_COLOR}✓{RESET}")
+        } else {
+            format!("{TODO_COLOR}○{RESET}")
+        };
+        let priority = match task.priority {
+            Some(Priority::High) => format!("{PRIORITY_HIGH_COLOR}▲{RESET}"),
+            Some(Priority::Medium) => format!("{PRIORITY_MEDIUM_COLOR}◆{RESET}"),
+            Some(Priority::Low) => format!("{PRIORITY_LOW_COLOR}▼{RESET}"),
+            None => " ".to_string(),
+        };
+        // A filtered view spans folders, so say where each task lives.
+        let task_label = if view.filter.is_some() {
+            truncate(&format!("{}: {}", task.folder, task.text), task_width)
+        } else {
+            truncate(&task.text, task_width)
+        };
+        let padded_label = format!("{:<width$}", task_label, width = task_width);
+        let id = if view.show_ids {
+            format!(
+                "{DIM}{VALUE_COLOR}{:<width$}{RESET}",
+                task.id,
+                width = id_width
+            )
+        } else {
+            String::new()
+        };
+        let task_colored = if task.done {
+            format!("{DONE_COLOR}{}{RESET}", padded_label)
+        } else {
+            format!("{TODO_COLOR}{}{RESET}", padded_label)
+        };
+        let date = if layout.show_created {
+            format!(" {DATE_COLOR}{}{RESET}", task.created_at.format("%d/%m/%y"))
+        } else {
+            String::new()
+        };
+        let due = match task.due {
+            _ if !layout.show_due => String::new(),
+            Some(due) => {
+                let color = if task.done {
+                    DATE_COLOR
+                } else {
+                    due_color(due, today)
+                };
+                format!(" {color}{}{RESET}", due.format("%d/%m/%y"))
+            }
+            None => format!(" {DIM}{DATE_COLOR}{:^8}{RESET}", "-"),
+        };
+        let number = format!("{VALUE_COLOR}{:>2}{RESET}", order + 1);
+        let row = format!(
+            "{pointer} {number}.  {status}{priority} {id}{task}{date}{due}",
+            pointer = pointer,
+            number = number,
+            status = status,
+            priority = priority,
+            id = id,
+            task = task_colored,
+            date = date,
+            due = due
+        );
+        frame.push(table_row(&row, layout.width));
+    }
+
+    for _ in visible_items.len()..rows {
+        frame.push(table_row("", layout.width));
+    }
+
+    frame.push(format!(
+        "{border}├{line}┤{reset}",
+        border = BORDER_COLOR,
+        line = &horizontal,
+        reset = RESET
+    ));
+    let scope = match &view.filter {
+        Some(tag) => format!("tagged {tag}"),
+        None => "in this folder".to_string(),
+    };
+    let summary_plain = if folder_count == 0 {
+        format!(" Showing 0 tasks {scope}.")
+    } else {
+        let start_display = start + 1;
+        let end_display = start + visible_items.len();
+        format!(
+            " Showing {}-{} of {} tasks {}.",
+            start_display, end_display, folder_count, scope
+        )
+    };
+    let overdue = folder_indices
+        .iter()
+        .filter(|idx| {
+            let task = &tasks[**idx];
+            !task.done && task.due.is_some_and(|due| due < today)
+        })
+        .count();
+    let summary = if overdue > 0 {
+        format!("{SUMMARY_COLOR}{summary_plain}{RESET} {OVERDUE_COLOR}{overdue} overdue{RESET}")
+    } else {
+        format!("{SUMMARY_COLOR}{summary_plain}{RESET}")
+    };
+    frame.push(table_row(&summary, layout.width));
+    frame.push(format!(
+        "{border}├{line}┤{reset}",
+        border = BORDER_COLOR,
+        line = &horizontal,
+        reset = RESET
+    ));
+
+    match mode {
+        Mode::Command => {
+            let label = " command: ";
+            let available = layout.width.saturating_sub(label.len());
+            let display = prompt_display(cmd, "(type a command and press Enter)", available);
+            frame.push(table_row(&format!("{label}{display}"), layout.width));
+            frame.push(table_row("", layout.width));
+        }
+        Mode::CommandInput(context) => match context {
+            CommandContext::Add { buffer } => {
+                frame.push(table_row(" command: add", layout.width));
+                let label = " add: ";
+                let available = layout.width.saturating_sub(label.len());
+                let display =
+                    prompt_display(buffer, "(describe the task, Enter to save)", available);
+                frame.push(table_row(&format!("{label}{display}"), layout.width));
+            }
+            CommandContext::Folder { buffer } => {
+                frame.push(table_row(" command: folder", layout.width));
+                let label = " folder: ";
+                let available = layout.width.saturating_sub(label.len());
+                let display =
+                    prompt_display(buffer, "(type folder name, Enter to switch)", available);
+                frame.push(table_row(&format!("{label}{display}"), layout.width));
+            }
+            CommandContext::Delete { buffer } => {
+                frame.push(table_row(" command: delete", layout.width));
+                let label = " delete: ";
+                let available = layout.width.saturating_sub(label.len());
+                let display = prompt_display(buffer, "(number or 'folder name')", available);
+                frame.push(table_row(&format!("{label}{display}"), layout.width));
+            }
+            CommandContext::Edit { task, buffer } => {
+                match task {
+                    Some(opened) => {
+                        // Its number now; the ID if it has left the folder.
+                        let number = folder_indices
+                            .iter()
+                            .position(|&idx| tasks[idx].id == opened.id)
+                            .map_or_else(|| opened.id.clone(), |pos| (pos + 1).to_string());
+                        frame.push(table_row(
+                            &format!(" command: edit {number}  {DIM}(Ctrl-E opens $EDITOR){RESET}"),
+                            layout.width,
+                        ))
+                    }
+                    None => frame.push(table_row(" command: edit", layout.width)),
+                }
+                let label = " edit: ";
+                let available = layout.width.saturating_sub(label.len());
+                let hint = match task {
+                    Some(_) => "(new text, Enter to save)",
+                    None => "(task number, Enter to edit)",
+                };
+                let display = prompt_display(buffer, hint, available);
+                frame.push(table_row(&format!("{label}{display}"), layout.width));
+            }
+            CommandContext::Filter { buffer } => {
+                frame.push(table_row(" command: filter", layout.width));
+                let label = " filter: ";
+                let available = layout.width.saturating_sub(label.len());
+                let display =
+                    prompt_display(buffer, "(tag like +urgent, or Enter to clear)", available);
+                frame.push(table_row(&format!("{label}{display}"), layout.width));
+            }
+            CommandContext::Profile { buffer, names } => {
+                frame.push(table_row(" command: profile", layout.width));
+                let label = " profile: ";
+                let available = layout.width.saturating_sub(label.len());
+                let hint = if buffer.is_empty() {
+                    if names.is_empty() {
+                        "(new profile name, Enter for the default list)".to_string()
+                    } else {
+                        format!("({}, or Enter for the default list)", names.join(", "))
+                    }
+                } else {
+                    String::new()
+                };
+                let display = prompt_display(buffer, &hint, available);
+                frame.push(table_row(&format!("{label}{display}"), layout.width));
+            }
+            CommandContext::Due { buffer } => {
+                frame.push(table_row(" command: due", layout.width));
+                let label = " due: ";
+                let available = layout.width.saturating_sub(label.len());
+                let display = prompt_display(
+                    buffer,
+                    "(number and date, e.g. '2 friday' or '2 none')",
+                    available,
+                );
+                frame.push(table_row(&format!("{label}{display}"), layout.width));
+            }
+        },
+        Mode::Navigate { .. } => {
+            frame.push(table_row(&format!(
+                "{ACCENT} navigate:{RESET} {VALUE_COLOR}↑/↓ move{RESET}, {VALUE_COLOR}d/space toggles done{RESET}, {VALUE_COLOR}Esc exits{RESET}"
+            ), layout.width));
+            frame.push(table_row(&format!(
+                "{ACCENT}          {RESET} {VALUE_COLOR}+/- priority{RESET}, {VALUE_COLOR}e edits{RESET}, {VALUE_COLOR}u undo{RESET}, {VALUE_COLOR}^R redo{RESET}"
+            ), layout.width));
+        }
+    }
+
+    let tip_variants = [
+        "Tip: Tap add for a quick idea, folder to regroup, delete to tidy up.",
+        "Tip: Folder keeps contexts neat; add logs tasks; delete clears the clutter.",
+        "Tip: Need a reset? add captures, folder jumps, delete prunes.",
+    ];
+    let hash = tick.wrapping_mul(6_364_136_223_846_793_005).rotate_left(7);
+    let tip_index = (hash % tip_variants.len() as u64) as usize;
+    // A failed save takes over the tip line until it is fixed.
+    let tip_line = match &status.save_error {
+        Some(err) => format!("{ERROR_COLOR}{BOLD}⚠ {err}{RESET}"),
+        None => format!("{TIP_TEXT}{DIM}{ITALIC}{}{RESET}", tip_variants[tip_index]),
+    };
+    frame.push(table_row(&tip_line, layout.width));
+
+    frame.push(format!(
+        "{border}╰{line}╯{reset}",
+        border = BORDER_COLOR,
+        line = &horizontal,
+        reset = RESET
+    ));
+    Frame { lines: frame }
+}
+
+/// The tip line changes every TIP_SECONDS; the main loop redraws when this does.
+pub fn tip_tick() -> u64 {
+    (Local::now().timestamp() / TIP_SECONDS).max(0) as u64
+}
+
+// What a prompt shows after its label: the line being typed, or while it
+// is empty a dimmed hint with the cursor on its first character.
+fn prompt_display(buffer: &LineEditor, hint: &str, width: usize) -> String {
+    if !buffer.is_empty() {
+        return buffer.render(width);
+    }
+    let mut chars = hint.chars();
+    match chars.next() {
+        Some(first) => format!("{CURSOR}{first}{RESET}{DIM}{}{RESET}", chars.as_str()),
+        None => String::new(),
+    }
+}
+
+fn truncate(text: &str, len: usize) -> String {
+    if text.chars().count() <= len {
+        return text.to_string();
+    }
+    if len <= 3 {
+        return ".".repeat(len);
+    }
+
+    let mut out = String::new();
+    for (i, c) in text.chars().enumerate() {
+        if i >= len - 3 {
+            out.push_str("...");
+            break;
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn clamp_display(content: &str, width: usize) -> (String, usize) {
+    let mut buf = String::with_capacity(content.len());
+    let mut visible = 0usize;
+    let mut chars = content.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            buf.push(ch);
+            for next in chars.by_ref() {
+                buf.push(next);
+                if next == 'm' {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if visible >= width {
+            continue;
+        }
+
+        // Stored text may hold tabs or newlines; keep each row on one line.
+        buf.push(if ch.is_control() { ' ' } else { ch });
+        visible += 1;
+    }
+    (buf, visible.min(width))
+}
+
+fn table_row(content: &str, width: usize) -> String {
+    let (prepared, visible) = clamp_display(content, width);
+    let padding = width.saturating_sub(visible);
+    let padding_str = " ".repeat(padding);
+    format!(
+        "{border}│{reset}{prepared}{padding}{border}│{reset}",
+        border = BORDER_COLOR,
+        reset = RESET,
+        prepared = prepared,
+        padding = padding_str
+    )
+}
+
+// ---- Frame buffer ----
+// One terminal cell: the character and the escape codes in effect for it.
+#[derive(Clone, PartialEq)]
+struct Cell {
+    ch: char,
+    style: String,
+}
+
+/// What is currently on the terminal. `draw` compares the next frame with it
+/// cell by cell and writes only the cells that changed. `None` means the
+/// terminal contents are unknown (first frame, resize, external editor), so
+/// the next draw clears the screen and paints everything.
+#[derive(Default)]
+pub struct Screen {
+    cells: Option<Vec<Vec<Cell>>>,
+}
+
+impl Screen {
+    /// Forgets what is on the terminal, so the next draw repaints it all.
+    pub fn invalidate(&mut self) {
+        self.cells = None;
+    }
+
+    /// Writes what changed since the last frame to `out` as escape codes.
+    pub fn draw(&mut self, frame: &Frame, writer: &mut impl Write) -> io::Result<()> {
+        let next: Vec<Vec<Cell>> = frame.lines.iter().map(|line| parse_cells(line)).collect();
+        let mut out = String::new();
+        let previous = match self.cells.take() {
+            Some(cells) => cells,
+            None => {
+                out.push_str("\x1b[2J");
+                Vec::new()
+            }
+        };
+
+        let blank = Cell {
+            ch: ' ',
+            style: String::new(),
+        };
+        // The style last sent to the terminal; unknown at the start of a draw.
+        let mut pen: Option<&str> = None;
+        for row in 0..next.len().max(previous.len()) {
+            let new_row = next.get(row).map(Vec::as_slice).unwrap_or_default();
+            let old_row = previous.get(row).map(Vec::as_slice).unwrap_or_default();
+            let mut cursor = None;
+            for col in 0..new_row.len().max(old_row.len()) {
+                let cell = new_row.get(col).unwrap_or(&blank);
+                if old_row.get(col).unwrap_or(&blank) == cell {
+                    continue;
+                }
+                if cursor != Some(col) {
+                    out.push_str(&format!("\x1b[{};{}H", row + 1, col + 1));
+                }
+                if pen != Some(cell.style.as_str()) {
+                    out.push_str(RESET);
+                    out.push_str(&cell.style);
+                    pen = Some(cell.style.as_str());
+                }
+                out.push(cell.ch);
+                cursor = Some(col + 1);
+            }
+        }
+        if pen.is_some() {
+            out.push_str(RESET);
+        }
+        // Park the cursor under the frame so later output starts on a fresh line.
+        out.push_str(&format!("\x1b[{};1H", next.len() + 1));
+
+        writer.write_all(out.as_bytes())?;
+        writer.flush()?;
+        self.cells = Some(next);
+        Ok(())
+    }
+}
+
+fn parse_cells(line: &str) -> Vec<Cell> {
+    let mut cells = Vec::new();
+    let mut style = String::new();
+    let mut chars = line.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            let mut code = String::from(ch);
+            for next in chars.by_ref() {
+                code.push(next);
+                if next == 'm' {
+                    break;
+                }
+            }
+            if code == RESET {
+                style.clear();
+            } else {
+                style.push_str(&code);
+            }
+            continue;
+        }
+        cells.push(Cell {
+            ch,
+            style: style.clone(),
+        });
+    }
+    cells
+}
+
+/// Applies a key press to the TUI state and saves any change to the list.
+/// Returns true when the key quits the TUI.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_key(
+    key: KeyEvent,
+    mode: &mut Mode,
+    buffer: &mut LineEditor,
+    commands: &mut CommandHistory,
+    tasks: &mut Vec<Task>,
+    view: &mut View,
+    storage: &mut Storage,
+    status: &mut Status,
+) -> bool {
+    let before = tasks.clone();
+    if matches!(mode, Mode::Command) {
+        match key.code {
+            KeyCode::Up => {
+                commands.older(buffer);
+                return false;
+            }
+            KeyCode::Down => {
+                commands.newer(buffer);
+                return false;
+            }
+            KeyCode::Enter => commands.record(buffer.text()),
+            _ => commands.reset(),
+        }
+    }
+    if key.code == KeyCode::Tab {
+        complete(mode, buffer, tasks, status);
+        return false;
+    }
+    // `add buy milk` does what `add`, Enter, `buy milk`, Enter does: the
+    // arguments go into the command's prompt and this Enter submits them.
+    if key.code == KeyCode::Enter && matches!(mode, Mode::Command) {
+        if let Some((word, args)) = buffer.text().trim().split_once(char::is_whitespace) {
+            if let Some(context) = command_context(word, LineEditor::from(args.trim())) {
+                *mode = Mode::CommandInput(context);
+                buffer.clear();
+            }
+        }
+    }
+    if let Some(undo) = history_request(&key, mode, buffer) {
+        buffer.clear();
+        if status.read_only {
+            status.message = vec![READ_ONLY_WARNING.to_string()];
+            return false;
+        }
+        let changed = if undo {
+            storage.history.undo(tasks)
+        } else {
+            storage.history.redo(tasks)
+        };
+        if !changed {
+            let word = if undo { "undo" } else { "redo" };
+            status.message = vec![format!("Nothing to {word}.")];
+        }
+        save(storage, &before, tasks, status);
+        return false;
+    }
+    if let Some(profile) = profile_request(&key, mode) {
+        switch_profile(profile, tasks, view, storage, status);
+        commands.reopen(storage.store.path());
+        *mode = Mode::Command;
+        buffer.clear();
+        return false;
+    }
+
+    let quit = match mode {
+        Mode::Command => handle_command(key, mode, buffer, tasks, view, status),
+        Mode::CommandInput(_) => handle_command_input(key, mode, buffer, tasks, view, status),
+        Mode::Navigate { .. } => {
+            handle_navigate(key, mode, tasks, view);
+            false
+        }
+    };
+    // A failed save stays on screen until a later one succeeds; that one
+    // writes the whole list, so it catches up on everything.
+    if *tasks != before {
+        if status.read_only {
+            // Nothing can be saved, so the change is taken back.
+            *tasks = before;
+            status.message = vec![READ_ONLY_WARNING.to_string()];
+        } else {
+            storage.history.record(&before, tasks);
+            save(storage, &before, tasks, status);
+        }
+    }
+    quit
+}
+
+// Tab: completes the command word at the command prompt, and a folder name
+// after `folder` or `delete folder`, typed inline or in their prompts. It
+// completes the end of the line, so the cursor has to be there. When several
+// names match and none can be completed further, the message lists them.
+fn complete(mode: &mut Mode, cmd_buf: &mut LineEditor, tasks: &[Task], status: &mut Status) {
+    let commands = || COMMANDS.iter().map(|c| c.to_string()).collect();
+    // `command_word` is true when completing a command rather than a folder.
+    let (buffer, start, candidates, command_word): (_, _, Vec<String>, _) = match mode {
+        Mode::Command => {
+            let text = cmd_buf.text();
+            let words: Vec<&str> = text.split_whitespace().collect();
+            let is =
+                |i: usize, word: &str| words.get(i).is_some_and(|w| w.eq_ignore_ascii_case(word));
+            let (start, candidates, command_word) = if argument_start(text, 1).is_none() {
+                (argument_start(text, 0), commands(), true)
+            } else if is(0, "folder") {
+                (argument_start(text, 1), folder_names(tasks), false)
+            } else if is(0, "delete") && is(1, "folder") {
+                (argument_start(text, 2), folder_names(tasks), false)
+            } else {
+                return;
+            };
+            (cmd_buf, start, candidates, command_word)
+        }
+        Mode::CommandInput(CommandContext::Folder { buffer }) => {
+            let start = argument_start(buffer.text(), 0);
+            (buffer, start, folder_names(tasks), false)
+        }
+        Mode::CommandInput(CommandContext::Delete { buffer }) => {
+            let is_folder = buffer
+                .text()
+                .split_whitespace()
+                .next()
+                .is_some_and(|w| w.eq_ignore_ascii_case("folder"));
+            if !is_folder {
+                return;
+            }
+            let start = argument_start(buffer.text(), 1);
+            (buffer, start, folder_names(tasks), false)
+        }
+        _ => return,
+    };
+    let Some(start) = start else {
+        return;
+    };
+    let text = buffer.text();
+    if buffer.cursor() != text.chars().count() {
+        return;
+    }
+
+    let (head, partial) = text.split_at(start);
+    let partial = partial.to_lowercase();
+    let matches: Vec<&String> = candidates
+        .iter()
+        .filter(|c| c.to_lowercase().starts_with(&partial))
+        .collect();
+    let completed = match matches.as_slice() {
+        [] => return,
+        // A command that takes arguments gets the space before them.
+        [only] if command_word && command_context(only, LineEditor::default()).is_some() => {
+            format!("{only} ")
+        }
+        [only] => only.to_string(),
+        [first, rest @ ..] => {
+            let common = rest.iter().fold(first.as_str(), |common, name| {
+                let len = common
+                    .char_indices()
+                    .zip(name.chars())
+                    .find(|((_, a), b)| a != b)
+                    .map_or(common.len().min(name.len()), |((i, _), _)| i);
+                &common[..len]
+            });
+            if common.chars().count() <= partial.chars().count() {
+                let names: Vec<&str> = matches.iter().map(|m| m.as_str()).collect();
+                status.message = vec![format!("Matches: {}", names.join(", "))];
+                return;
+            }
+            common.to_string()
+        }
+    };
+    *buffer = LineEditor::from(format!("{head}{completed}"));
+}
+
+// Where the text after the first `n` words of `text` starts; None unless
+// whitespace follows those words.
+fn argument_start(text: &str, n: usize) -> Option<usize> {
+    let mut rest = text.trim_start();
+    for _ in 0..n {
+        let end = rest.find(char::is_whitespace)?;
+        rest = rest[end..].trim_start();
+    }
+    Some(text.len() - rest.len())
+}
+
+/// Whether `key` hands the terminal to $EDITOR (Ctrl-E while editing a
+/// task). That only refills the edit prompt; the list is saved by the Enter
+/// that follows, so the caller need not hold the write lock while the editor
+/// runs.
+pub fn opens_editor(key: &KeyEvent, mode: &Mode) -> bool {
+    key.code == KeyCode::Char('e')
+        && key.modifiers.contains(KeyModifiers::CONTROL)
+        && matches!(
+            mode,
+            Mode::CommandInput(CommandContext::Edit { task: Some(_), .. })
+        )
+}
+
+/// Inserts pasted text at the cursor of the prompt being typed in. A paste
+/// never submits the prompt, even if it ends in a line break; pastes while
+/// navigating are ignored.
+pub fn handle_paste(text: &str, mode: &mut Mode, buffer: &mut LineEditor) {
+    match mode {
+        Mode::Command => buffer.insert_str(text),
+        Mode::CommandInput(context) => context.buffer_mut().insert_str(text),
+        Mode::Navigate { .. } => {}
+    }
+}
+
+// The profile to switch to when Enter is pressed in the `profile` prompt:
+// Some(None) for the default list (an empty name or "default"). Invalid
+// names keep the prompt open.
+fn profile_request(key: &KeyEvent, mode: &Mode) -> Option<Option<String>> {
+    let Mode::CommandInput(CommandContext::Profile { buffer, .. }) = mode else {
+        return None;
+    };
+    if key.code != KeyCode::Enter {
+        return None;
+    }
+    match buffer.text().trim() {
+        "" | "default" => Some(None),
+        name if valid_profile_name(name) => Some(Some(name.to_string())),
+        _ => None,
+    }
+}
+
+// Opens another profile's list in place of the current one. If it cannot be
+// opened the current list stays and the banner says why.
+fn switch_profile(
+    profile: Option<String>,
+    tasks: &mut Vec<Task>,
+    view: &mut View,
+    storage: &mut Storage,
+    status: &mut Status,
+) {
+    let opened = try_open_store(storage.file.clone(), profile.as_deref()).and_then(|mut store| {
+        // Loading can rewrite the file (an upgrade, quarantined lines, new IDs).
+        let _lock = lock_for_write(store.path())
+            .map_err(|err| format!("cannot lock {}: {err}", store.path().display()))?;
+        let loaded = store
+            .load()
+            .map_err(|err| format!("cannot load tasks: {err}"))?;
+        Ok((store, loaded))
+    });
+    match opened {
+        Ok((mut store, loaded)) => {
+            status.load_warnings = store.take_warnings();
+            status.save_error = None;
+            storage.history = store.load_history();
+            storage.store = store;
+            storage.synced = loaded.clone();
+            status.read_only = !storage.lock_session();
+            *tasks = loaded;
+            view.folder = first_folder(tasks);
+            view.filter = None;
+            view.profile = profile;
+        }
+        Err(err) => {
+            let name = profile.as_deref().unwrap_or("default");
+            status.load_warnings = vec![format!("⚠ Could not open profile {name}: {err}")];
+        }
+    }
+}
+
+// Some(true) for undo, Some(false) for redo: `u` / Ctrl-R while navigating,
+// Ctrl-R or the `undo` / `redo` commands at the command prompt.
+fn history_request(key: &KeyEvent, mode: &Mode, buffer: &LineEditor) -> Option<bool> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match (mode, key.code) {
+        (Mode::Navigate { .. }, KeyCode::Char('u')) if !ctrl => Some(true),
+        (Mode::Navigate { .. } | Mode::Command, KeyCode::Char('r')) if ctrl => Some(false),
+        (Mode::Command, KeyCode::Enter) => match buffer.text().trim().to_lowercase().as_str() {
+            "undo" => Some(true),
+            "redo" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Every word the command prompt understands.
+const COMMANDS: &[&str] = &[
+    "add", "folder", "delete", "due", "edit", "filter", "profile", "sort", "ids", "undo", "redo",
+];
+
+// The prompt a command opens, holding `buffer`; None for commands that act
+// straight away (and for words that are not commands).
+fn command_context(word: &str, buffer: LineEditor) -> Option<CommandContext> {
+    let context = match word.to_lowercase().as_str() {
+        "add" => CommandContext::Add { buffer },
+        "folder" => CommandContext::Folder { buffer },
+        "delete" => CommandContext::Delete { buffer },
+        "due" => CommandContext::Due { buffer },
+        "edit" => CommandContext::Edit { task: None, buffer },
+        "filter" => CommandContext::Filter { buffer },
+        "profile" => CommandContext::Profile {
+            buffer,
+            names: profile_names(),
+        },
+        _ => return None,
+    };
+    Some(context)
+}
+
+fn handle_command(
+    key: KeyEvent,
+    mode: &mut Mode,
+    buf: &mut LineEditor,
+    tasks: &mut Vec<Task>,
+    view: &mut View,
+    status: &mut Status,
+) -> bool {
+    match key.code {
+        KeyCode::Esc => buf.clear(),
+        // The shortcuts only work on an empty prompt, so arguments such as
+        // `folder Quotes` can contain their letters.
+        KeyCode::Char('D') if key.modifiers.contains(KeyModifiers::SHIFT) && buf.is_empty() => {
+            let folder_len = folder_indices(tasks, view).len();
+            if folder_len > 0 {
+                *mode = Mode::Navigate {
+                    selected: folder_len - 1,
+                };
+            }
+        }
+        KeyCode::Char('q') if buf.is_empty() => return true,
+        KeyCode::Enter => {
+            let cmd = buf.text().trim().to_lowercase();
+            if let Some(context) = command_context(&cmd, LineEditor::default()) {
+                *mode = Mode::CommandInput(context);
+            } else if cmd == "sort" {
+                apply_action(CommandAction::ToggleSort, tasks, view);
+            } else if cmd == "ids" {
+                apply_action(CommandAction::ToggleIds, tasks, view);
+            } else if !cmd.is_empty() {
+                // Keep what was typed so it can be corrected.
+                status.message = command_error(&cmd);
+                return false;
+            }
+            buf.clear();
+        }
+        _ => {
+            buf.handle_key(&key);
+        }
+    }
+    false
+}
+
+// What the prompt's message says about a command line that did nothing.
+fn command_error(cmd: &str) -> Vec<String> {
+    let word = cmd.split_whitespace().next().unwrap_or_default();
+    if COMMANDS.contains(&word) {
+        vec![format!("⚠ '{word}' takes no arguments.")]
+    } else {
+        vec![
+            format!("⚠ Unknown command '{word}'. Commands:"),
+            format!("  {}", COMMANDS.join(", ")),
+        ]
+    }
+}
+
+fn handle_command_input(
+    key: KeyEvent,
+    mode: &mut Mode,
+    cmd_buf: &mut LineEditor,
+    tasks: &mut Vec<Task>,
+    view: &mut View,
+    status: &mut Status,
+) -> bool {
+    let mut exit_to_command = false;
+    let mut action: Option<CommandAction> = None;
+
+    match mode {
+        Mode::CommandInput(CommandContext::Add { buffer }) => match key.code {
+            KeyCode::Esc => {
+                buffer.clear();
+                exit_to_command = true;
+            }
+            KeyCode::Char('b') if key.modifiers.is_empty() && buffer.is_empty() => {
+                exit_to_command = true;
+            }
+            KeyCode::Enter => {
+                let text = buffer.text().trim();
+                let only_tokens = task_text(text, Local::now().date_naive()).is_empty();
+                if !text.is_empty() && only_tokens {
+                    // Kept, so the text can be typed in front of the tokens.
+                    status.message = vec![NO_TEXT_MESSAGE.to_string()];
+                } else if !text.is_empty() {
+                    action = Some(CommandAction::AddTask(text.to_string()));
+                    buffer.clear();
+                    exit_to_command = true;
+                }
+            }
+            _ => {
+                buffer.handle_key(&key);
+            }
+        },
+        Mode::CommandInput(CommandContext::Folder { buffer }) => match key.code {
+            KeyCode::Esc => {
+                buffer.clear();
+                exit_to_command = true;
+            }
+            KeyCode::Char('b') if key.modifiers.is_empty() && buffer.is_empty() => {
+                exit_to_command = true;
+            }
+            KeyCode::Enter => {
+                let name = buffer.text().trim();
+                if !name.is_empty() {
+                    action = Some(CommandAction::SwitchFolder(name.to_string()));
+                    buffer.clear();
+                    exit_to_command = true;
+                }
+            }
+            _ => {
+                buffer.handle_key(&key);
+            }
+        },
+        Mode::CommandInput(CommandContext::Delete { buffer }) => match key.code {
+            KeyCode::Esc => {
+                buffer.clear();
+                exit_to_command = true;
+            }
+            KeyCode::Char('b') if key.modifiers.is_empty() && buffer.is_empty() => {
+                exit_to_command = true;
+            }
+            KeyCode::Enter => {
+                let input = buffer.text().trim();
+                if !input.is_empty() {
+                    let mut parts = input.split_whitespace();
+                    let head = parts.next().unwrap_or("");
+                    if head.eq_ignore_ascii_case("folder") {
+                        let target = parts.collect::<Vec<_>>().join(" ");
+                        let target = if target.is_empty() {
+                            view.folder.clone()
+                        } else {
+                            target
+                        };
+                        action = Some(CommandAction::DeleteFolder(target));
+                        buffer.clear();
+                        exit_to_command = true;
+                    } else if let Some(target) = parse_task_ref(input) {
+                        action = Some(CommandAction::DeleteTask(target));
+                        buffer.clear();
+                        exit_to_command = true;
+                    }
+                }
+            }
+            _ => {
+                buffer.handle_key(&key);
+            }
+        },
+        Mode::CommandInput(CommandContext::Edit { task, buffer }) => match key.code {
+            KeyCode::Esc => {
+                buffer.clear();
+                exit_to_command = true;
+            }
+            KeyCode::Char('b') if key.modifiers.is_empty() && buffer.is_empty() => {
+                exit_to_command = true;
+            }
+            KeyCode::Char('e')
+                if key.modifiers.contains(KeyModifiers::CONTROL) && task.is_some() =>
+            {
+                if let Ok(text) = edit_in_external_editor(buffer.text()) {
+                    *buffer = LineEditor::from(text);
+                }
+            }
+            KeyCode::Enter => match task {
+                Some(opened) => {
+                    let text = buffer.text().trim();
+                    if !text.is_empty() {
+                        let target = edit_target(opened, tasks, status);
+                        action = Some(CommandAction::EditTask(target, text.to_string()));
+                        buffer.clear();
+                        exit_to_command = true;
+                    }
+                }
+                // `3 fix typo` (or `edit 3 fix typo` on one line) opens task
+                // 3 with the new text already in place of the old.
+                None => {
+                    let input = buffer.text().trim();
+                    let (number, text) = input.split_once(' ').unwrap_or((input, ""));
+                    let target = parse_task_ref(number);
+                    if let Some(idx) = target.and_then(|t| folder_task_index(tasks, view, &t)) {
+                        *task = Some(tasks[idx].clone());
+                        *buffer = match text.trim() {
+                            "" => LineEditor::from(tasks[idx].text.as_str()),
+                            text => LineEditor::from(text),
+                        };
+                    }
+                }
+            },
+            _ => {
+                buffer.handle_key(&key);
+            }
+        },
+        Mode::CommandInput(CommandContext::Filter { buffer }) => match key.code {
+            KeyCode::Esc => {
+                buffer.clear();
+                exit_to_command = true;
+            }
+            KeyCode::Char('b') if key.modifiers.is_empty() && buffer.is_empty() => {
+                exit_to_command = true;
+            }
+            KeyCode::Enter => {
+                let tag = buffer.text().trim();
+                let filter = if tag.is_empty() {
+                    None
+                } else {
+                    Some(tag.to_string())
+                };
+                action = Some(CommandAction::SetFilter(filter));
+                buffer.clear();
+                exit_to_command = true;
+            }
+            _ => {
+                buffer.handle_key(&key);
+            }
+        },
+        // Enter is handled by `profile_request`, which can replace the store.
+        Mode::CommandInput(CommandContext::Profile { buffer, .. }) => match key.code {
+            KeyCode::Esc => {
+                buffer.clear();
+                exit_to_command = true;
+            }
+            KeyCode::Char('b') if key.modifiers.is_empty() && buffer.is_empty() => {
+                exit_to_command = true;
+            }
+            _ => {
+                buffer.handle_key(&key);
+            }
+        },
+        Mode::CommandInput(CommandContext::Due { buffer }) => match key.code {
+            KeyCode::Esc => {
+                buffer.clear();
+                exit_to_command = true;
+            }
+            KeyCode::Char('b') if key.modifiers.is_empty() && buffer.is_empty() => {
+                exit_to_command = true;
+            }
+            KeyCode::Enter => {
+                let mut parts = buffer.text().split_whitespace();
+                let number = parts.next().and_then(parse_task_ref);
+                let when = parts.collect::<Vec<_>>().join(" ");
+                if let (Some(number), Some(due)) = (number, parse_due_setting(&when)) {
+                    action = Some(CommandAction::SetDue(number, due));
+                    buffer.clear();
+                    exit_to_command = true;
+                }
+            }
+            _ => {
+                buffer.handle_key(&key);
+            }
+        },
+        _ => {}
+    }
+
+    if let Some(action) = action {
+        apply_action(action, tasks, view);
+    }
+
+    if exit_to_command {
+        *mode = Mode::Command;
+        cmd_buf.clear();
+    }
+
+    false
+}
+
+// The task an edit opened on `opened` is saved to. If the task's text was
+// changed elsewhere while the prompt was open, the edit goes to a copy under
+// a new ID, next to it, so neither version is lost; if the task was deleted
+// elsewhere, it is put back. Either way the message says so.
+fn edit_target(opened: &Task, tasks: &mut Vec<Task>, status: &mut Status) -> TaskRef {
+    match tasks.iter().position(|t| t.id == opened.id) {
+        Some(idx) if tasks[idx].text == opened.text => TaskRef::Id(opened.id.clone()),
+        Some(idx) => {
+            let mut copy = tasks[idx].clone();
+            copy.id = new_task_id(tasks);
+            status.message =
+                vec!["⚠ The task was changed elsewhere meanwhile; kept both versions.".to_string()];
+            let target = TaskRef::Id(copy.id.clone());
+            tasks.insert(idx + 1, copy);
+            target
+        }
+        None => {
+            status.message =
+                vec!["⚠ The task was deleted elsewhere meanwhile; kept your version.".to_string()];
+            tasks.push(opened.clone());
+            TaskRef::Id(opened.id.clone())
+        }
+    }
+}
+
+fn handle_navigate(key: KeyEvent, mode: &mut Mode, tasks: &mut [Task], view: &View) {
+    let Mode::Navigate { selected } = mode else {
+        return;
+    };
+
+    let indices = folder_indices(tasks, view);
+
+    if indices.is_empty() {
+        *mode = Mode::Command;
+        return;
+    }
+
+    let max_index = indices.len() - 1;
+    if *selected > max_index {
+        *selected = max_index;
+    }
+
+    match key.code {
+        KeyCode::Esc => *mode = Mode::Command,
+        KeyCode::Up | KeyCode::Char('k') if *selected > 0 => *selected -= 1,
+        KeyCode::Down | KeyCode::Char('j') if *selected < max_index => *selected += 1,
+        KeyCode::Char('d' | ' ') => {
+            if let Some(&task_idx) = indices.get(*selected) {
+                if let Some(task) = tasks.get_mut(task_idx) {
+                    set_done(task, !task.done);
+                }
+            }
+        }
+        KeyCode::Char('e') => {
+            if let Some(&task_idx) = indices.get(*selected) {
+                *mode = Mode::CommandInput(CommandContext::Edit {
+                    task: Some(tasks[task_idx].clone()),
+                    buffer: LineEditor::from(tasks[task_idx].text.as_str()),
+                });
+            }
+        }
+        KeyCode::Char(c @ ('+' | '-')) => {
+            if let Some(&task_idx) = indices.get(*selected) {
+                let task = &mut tasks[task_idx];
+                task.priority = if c == '+' {
+                    raise_priority(task.priority)
+                } else {
+                    lower_priority(task.priority)
+                };
+                // Keep the cursor on the task when the sort order moves it.
+                if let Some(pos) = folder_indices(tasks, view)
+                    .iter()
+                    .position(|&idx| idx == task_idx)
+                {
+                    *selected = pos;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// Lets the user edit long text in $VISUAL/$EDITOR. The terminal goes back to
+// its normal state while the editor runs; line breaks are folded into spaces
+// because a task is a single line.
+fn edit_in_external_editor(text: &str) -> io::Result<String> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no editor configured"))?;
+
+    let dir = private_temp_dir()?;
+    let path = dir.join("task.txt");
+    let written = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{text}"));
+    if let Err(err) = written {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(err);
+    }
+
+    restore_terminal();
+    let status = process::Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status();
+    setup_terminal()?;
+
+    let edited = match status {
+        Ok(status) if status.success() => fs::read_to_string(&path),
+        Ok(_) => Err(io::Error::other("editor exited with an error")),
+        Err(err) => Err(err),
+    };
+    let _ = fs::remove_dir_all(&dir);
+    Ok(edited?.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+// A new directory under the system temp directory that only this user can
+// enter. Creating it fails rather than reusing anything already there, so no
+// one else can plant a file or symlink where the task text is written.
+fn private_temp_dir() -> io::Result<PathBuf> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    let mut attempt = 0u32;
+    loop {
+        let dir = env::temp_dir().join(format!("pastel_todo-edit-{}-{attempt}", process::id()));
+        match builder.create(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => {
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn due_color(due: NaiveDate, today: NaiveDate) -> &'static str {
+    let days_left = (due - today).num_days();
+    if days_left < 0 {
+        OVERDUE_COLOR
+    } else if days_left == 0 {
+        DUE_TODAY_COLOR
+    } else if days_left <= DUE_SOON_DAYS {
+        DUE_SOON_COLOR
+    } else {
+        DATE_COLOR
+    }
+}
+
+// Saves the change from `before` to `tasks`. A failed save stays on the tip
+// line until one works; warnings such as a failed backup go to the banner.
+fn save(storage: &mut Storage, before: &[Task], tasks: &[Task], status: &mut Status) {
+    status.save_error = storage.save(before, tasks, status.save_error.is_some());
+    status.load_warnings.extend(storage.store.take_warnings());
+}
+
+// ---- External changes ----
+// A script or a second instance may change the stored list while the TUI
+// is open. The main loop checks before every key press and on idle ticks,
+// and merges the stored list with the one on screen, so the next save does
+// not clobber the other change.
+
+/// Reloads the list if it changed elsewhere. Returns true when it did.
+pub fn reload_if_changed(
+    tasks: &mut Vec<Task>,
+    storage: &mut Storage,
+    status: &mut Status,
+) -> bool {
+    if !storage.store.changed_elsewhere() {
+        return false;
+    }
+    let theirs = match storage.store.load() {
+        Ok(theirs) => theirs,
+        Err(err) => {
+            status.save_error = Some(format!("Could not reload tasks: {err}"));
+            return false;
+        }
+    };
+    status.load_warnings.extend(storage.store.take_warnings());
+    storage.history = storage.store.load_history();
+    let (merged, conflicts) = merge_tasks(&storage.synced, tasks, &theirs);
+    storage.synced = theirs.clone();
+    *tasks = merged;
+    if *tasks != theirs {
+        storage.history.record(&theirs, tasks);
+        save(storage, &theirs, tasks, status);
+    }
+    if !conflicts.is_empty() {
+        status
+            .load_warnings
+            .push("⚠ Tasks were changed here and elsewhere at the same time:".to_string());
+        status.load_warnings.extend(conflicts);
+    }
+    true
+}
@@ -0,0 +1,553 @@
+// Snapshot tests for the TUI. Key presses go through `handle_key` exactly as
+// the terminal would send them, and the frames `render` draws are compared,
+// as plain text, with the files in tests/snapshots/. After an intended UI
+// change, rerun with UPDATE_SNAPSHOTS=1 to rewrite them and review the diff.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use pastel_todo::tui::{
+    handle_key, handle_paste, layout_for, reload_if_changed, render, CommandHistory, Layout,
+    LineEditor, Mode, Status,
+};
+use pastel_todo::{History, Priority, SortOrder, Storage, Task, TaskStore, View, DEFAULT_FOLDER};
+use std::cell::Cell;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// Keeps the list in memory so tests touch neither the data directory nor
+// each other.
+struct MemoryStore {
+    path: PathBuf,
+    tasks: Vec<Task>,
+    // Set by `Tui::change_elsewhere`, as a file's new mtime would be.
+    changed: Rc<Cell<bool>>,
+}
+
+impl MemoryStore {
+    fn position(&self, id: &str) -> io::Result<usize> {
+        self.tasks
+            .iter()
+            .position(|t| t.id == id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, id.to_string()))
+    }
+}
+
+impl TaskStore for MemoryStore {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&mut self) -> io::Result<Vec<Task>> {
+        Ok(self.tasks.clone())
+    }
+
+    fn insert(&mut self, index: usize, task: &Task) -> io::Result<()> {
+        self.tasks.insert(index.min(self.tasks.len()), task.clone());
+        Ok(())
+    }
+
+    fn update(&mut self, id: &str, task: &Task) -> io::Result<()> {
+        let i = self.position(id)?;
+        self.tasks[i] = task.clone();
+        Ok(())
+    }
+
+    fn delete(&mut self, id: &str) -> io::Result<()> {
+        let i = self.position(id)?;
+        self.tasks.remove(i);
+        Ok(())
+    }
+
+    fn replace(&mut self, tasks: &[Task]) -> io::Result<()> {
+        self.tasks = tasks.to_vec();
+        Ok(())
+    }
+
+    // The undo history lives in `Storage` for the length of a test.
+    fn load_history(&mut self) -> History {
+        History::default()
+    }
+
+    fn save_history(&mut self, _history: &History) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn changed_elsewhere(&mut self) -> bool {
+        self.changed.replace(false)
+    }
+}
+
+// Everything the main loop keeps between key presses.
+struct Tui {
+    tasks: Vec<Task>,
+    view: View,
+    mode: Mode,
+    buffer: LineEditor,
+    commands: CommandHistory,
+    storage: Storage,
+    status: Status,
+    layout: Layout,
+    changed_elsewhere: Rc<Cell<bool>>,
+}
+
+impl Tui {
+    fn new(cols: u16, lines: u16) -> Self {
+        let changed_elsewhere = Rc::new(Cell::new(false));
+        let store = MemoryStore {
+            path: PathBuf::from("memory"),
+            tasks: Vec::new(),
+            changed: changed_elsewhere.clone(),
+        };
+        Tui {
+            tasks: Vec::new(),
+            view: View {
+                folder: DEFAULT_FOLDER.to_string(),
+                sort: SortOrder::Added,
+                filter: None,
+                show_ids: false,
+                profile: None,
+            },
+            mode: Mode::Command,
+            buffer: LineEditor::default(),
+            // Kept in memory, so nothing is written next to the list.
+            commands: CommandHistory::default(),
+            storage: Storage::new(Box::new(store), &[], None),
+            status: Status::default(),
+            layout: layout_for(cols, lines),
+            changed_elsewhere,
+        }
+    }
+
+    // Changes the stored list as another instance would. The next key press
+    // reloads it, as in the main loop.
+    fn change_elsewhere(&mut self, change: impl FnOnce(&mut Vec<Task>)) {
+        let mut tasks = self.storage.store.load().unwrap();
+        change(&mut tasks);
+        self.storage.store.replace(&tasks).unwrap();
+        self.changed_elsewhere.set(true);
+    }
+
+    fn press_with(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        // Like the main loop, any key dismisses the banner and the message.
+        self.status.load_warnings.clear();
+        self.status.message.clear();
+        reload_if_changed(&mut self.tasks, &mut self.storage, &mut self.status);
+        let quit = handle_key(
+            KeyEvent::new(code, modifiers),
+            &mut self.mode,
+            &mut self.buffer,
+            &mut self.commands,
+            &mut self.tasks,
+            &mut self.view,
+            &mut self.storage,
+            &mut self.status,
+        );
+        assert!(!quit, "{code:?} quit the TUI");
+    }
+
+    fn press(&mut self, code: KeyCode) {
+        self.press_with(code, KeyModifiers::NONE);
+    }
+
+    fn type_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.press(KeyCode::Char(c));
+        }
+    }
+
+    // Types `text` and presses Enter.
+    fn enter(&mut self, text: &str) {
+        self.type_text(text);
+        self.press(KeyCode::Enter);
+    }
+
+    fn paste(&mut self, text: &str) {
+        self.status.load_warnings.clear();
+        self.status.message.clear();
+        handle_paste(text, &mut self.mode, &mut self.buffer);
+    }
+
+    fn add(&mut self, text: &str) {
+        self.enter("add");
+        self.enter(text);
+    }
+
+    // The frame as plain text. The tip is pinned, and dates, which depend on
+    // the day the test runs, are masked.
+    fn frame(&self) -> String {
+        let frame = render(
+            &self.tasks,
+            &self.view,
+            &self.mode,
+            &self.buffer,
+            &self.layout,
+            &self.status,
+            0,
+        );
+        mask_dates(&frame.text())
+    }
+}
+
+// Replaces dd/mm/yy dates with DD/MM/YY.
+fn mask_dates(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let pattern = "00/00/00";
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let is_date = chars.len() - i >= pattern.len()
+            && pattern.chars().enumerate().all(|(j, p)| match p {
+                '/' => chars[i + j] == '/',
+                _ => chars[i + j].is_ascii_digit(),
+            });
+        if is_date {
+            out.push_str("DD/MM/YY");
+            i += pattern.len();
+        } else {
+            out.push(chars[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{name}.txt"));
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "cannot read {}: {err}; run with UPDATE_SNAPSHOTS=1 to create it",
+            path.display()
+        )
+    });
+    assert!(
+        expected == actual,
+        "frame differs from {}\n--- expected\n{expected}--- actual\n{actual}",
+        path.display()
+    );
+}
+
+#[test]
+fn empty_list() {
+    let tui = Tui::new(80, 24);
+    assert_snapshot("empty_list", &tui.frame());
+}
+
+#[test]
+fn add_prompt() {
+    let mut tui = Tui::new(80, 24);
+    tui.enter("add");
+    tui.type_text("get mi");
+    assert_snapshot("add_prompt", &tui.frame());
+}
+
+#[test]
+fn added_tasks() {
+    let mut tui = Tui::new(80, 24);
+    tui.add("get milk pri:high");
+    tui.add("call the plumber due:2099-12-31 #home");
+    tui.add("water the plants pri:low");
+    assert_snapshot("added_tasks", &tui.frame());
+}
+
+#[test]
+fn navigate_and_complete() {
+    let mut tui = Tui::new(80, 24);
+    tui.add("get milk");
+    tui.add("call the plumber");
+    tui.add("water the plants");
+    tui.press_with(KeyCode::Char('D'), KeyModifiers::SHIFT);
+    tui.press(KeyCode::Up);
+    tui.press(KeyCode::Char(' '));
+    tui.press(KeyCode::Char('+'));
+    assert_snapshot("navigate_and_complete", &tui.frame());
+}
+
+#[test]
+fn folder_switch_delete_and_undo() {
+    let mut tui = Tui::new(80, 24);
+    tui.add("get milk");
+    tui.enter("folder");
+    tui.enter("work");
+    tui.add("ship the release");
+    tui.add("review pull requests");
+    tui.enter("delete");
+    tui.enter("1");
+    assert_snapshot("folder_after_delete", &tui.frame());
+    tui.enter("undo");
+    assert_snapshot("folder_after_undo", &tui.frame());
+}
+
+#[test]
+fn undo_with_no_history_says_so() {
+    let mut tui = Tui::new(80, 24);
+    tui.add("get milk");
+    tui.press_with(KeyCode::Char('D'), KeyModifiers::SHIFT);
+    tui.press(KeyCode::Char('u'));
+    tui.press(KeyCode::Char('u'));
+    assert_snapshot("nothing_to_undo", &tui.frame());
+    tui.press_with(KeyCode::Char('r'), KeyModifiers::CONTROL);
+    tui.press_with(KeyCode::Char('r'), KeyModifiers::CONTROL);
+    assert!(tui.frame().contains("Nothing to redo."));
+    assert_eq!(tui.tasks.len(), 1);
+}
+
+#[test]
+fn priority_sort_and_tag_filter() {
+    let mut tui = Tui::new(80, 24);
+    tui.add("low one pri:low +site");
+    tui.add("no priority");
+    tui.add("high one pri:high +site");
+    tui.enter("sort");
+    assert_snapshot("priority_sort", &tui.frame());
+    tui.enter("filter");
+    tui.enter("+site");
+    assert_snapshot("tag_filter", &tui.frame());
+}
+
+#[test]
+fn inline_command_arguments() {
+    let mut tui = Tui::new(80, 24);
+    tui.enter("add quarterly report due:2099-12-31");
+    tui.enter("folder Quotes");
+    tui.enter("add Dorothy Parker");
+    tui.enter("add Oscar Wilde");
+    tui.enter("delete 1");
+    assert_snapshot("inline_commands", &tui.frame());
+    tui.enter("delete folder Quotes");
+    assert_snapshot("inline_delete_folder", &tui.frame());
+}
+
+#[test]
+fn inline_arguments_that_do_not_parse_stay_in_the_prompt() {
+    let mut tui = Tui::new(80, 24);
+    tui.enter("due someday");
+    assert_snapshot("inline_bad_arguments", &tui.frame());
+}
+
+#[test]
+fn unknown_command_is_reported() {
+    let mut tui = Tui::new(80, 24);
+    tui.enter("ad milk");
+    assert_snapshot("unknown_command", &tui.frame());
+    tui.press(KeyCode::Esc);
+    tui.enter("sort please");
+    assert_snapshot("command_without_arguments", &tui.frame());
+}
+
+#[test]
+fn add_needs_text_besides_tokens() {
+    let mut tui = Tui::new(80, 24);
+    tui.enter("add due:tomorrow pri:high");
+    assert_snapshot("add_only_tokens", &tui.frame());
+    assert!(tui.tasks.is_empty());
+    tui.press(KeyCode::Home);
+    tui.enter("pay rent ");
+    assert_eq!(tui.tasks[0].text, "pay rent");
+}
+
+#[test]
+fn editing_inside_the_line() {
+    let mut tui = Tui::new(80, 24);
+    tui.enter("add");
+    tui.type_text("get milk");
+    for _ in 0.."milk".len() {
+        tui.press(KeyCode::Left);
+    }
+    tui.type_text("oat ");
+    tui.press(KeyCode::Home);
+    for _ in 0.."get ".len() {
+        tui.press(KeyCode::Delete);
+    }
+    tui.press(KeyCode::End);
+    tui.press_with(KeyCode::Char('w'), KeyModifiers::CONTROL);
+    tui.type_text("bread");
+    assert_snapshot("line_editing", &tui.frame());
+    tui.press(KeyCode::Enter);
+    tui.type_text("add something");
+    tui.press_with(KeyCode::Char('u'), KeyModifiers::CONTROL);
+    tui.type_text("ortx");
+    tui.press(KeyCode::Backspace);
+    tui.press(KeyCode::Home);
+    tui.type_text("s");
+    assert_snapshot("line_editing_command", &tui.frame());
+    tui.press(KeyCode::Enter);
+    assert_snapshot("line_editing_after_command", &tui.frame());
+}
+
+#[test]
+fn long_input_scrolls_sideways() {
+    let mut tui = Tui::new(40, 24);
+    tui.enter("add");
+    tui.type_text("the quick brown fox jumps over the lazy dog again");
+    assert_snapshot("scrolled_to_end", &tui.frame());
+    tui.press(KeyCode::Home);
+    assert_snapshot("scrolled_to_start", &tui.frame());
+    for _ in 0..40 {
+        tui.press(KeyCode::Right);
+    }
+    assert_snapshot("scrolled_to_middle", &tui.frame());
+}
+
+#[test]
+fn pasted_text_is_inserted_at_the_cursor() {
+    let mut tui = Tui::new(80, 24);
+    tui.enter("add");
+    tui.type_text("call  today");
+    for _ in 0.." today".len() {
+        tui.press(KeyCode::Left);
+    }
+    tui.paste("the plumber\nabout the sink");
+    assert_snapshot("pasted_text", &tui.frame());
+    tui.press(KeyCode::Enter);
+    tui.paste("add get milk\n");
+    tui.press(KeyCode::Enter);
+    assert_snapshot("pasted_command", &tui.frame());
+}
+
+#[test]
+fn up_and_down_recall_commands() {
+    let mut tui = Tui::new(80, 24);
+    tui.enter("add get milk");
+    tui.enter("sort");
+    tui.type_text("fil");
+    tui.press(KeyCode::Up);
+    tui.press(KeyCode::Up);
+    assert_snapshot("history_recalled", &tui.frame());
+    tui.press(KeyCode::Down);
+    tui.press(KeyCode::Down);
+    assert_snapshot("history_draft_restored", &tui.frame());
+    tui.press(KeyCode::Esc);
+    tui.press(KeyCode::Up);
+    tui.press(KeyCode::Up);
+    tui.press(KeyCode::End);
+    tui.type_text(" again");
+    tui.press(KeyCode::Enter);
+    assert_snapshot("history_edited_and_run", &tui.frame());
+}
+
+#[test]
+fn tab_completes_commands_and_folders() {
+    let mut tui = Tui::new(80, 24);
+    tui.enter("folder Quotes");
+    tui.enter("add Dorothy Parker");
+    tui.enter("folder Quarterly");
+    tui.enter("add draft the report");
+    tui.type_text("fo");
+    tui.press(KeyCode::Tab);
+    tui.type_text("q");
+    tui.press(KeyCode::Tab);
+    tui.press(KeyCode::Tab);
+    assert_snapshot("tab_lists_matches", &tui.frame());
+    tui.type_text("o");
+    tui.press(KeyCode::Tab);
+    tui.press(KeyCode::Enter);
+    assert_snapshot("tab_completed_folder", &tui.frame());
+    tui.enter("folder");
+    tui.type_text("qua");
+    tui.press(KeyCode::Tab);
+    tui.press(KeyCode::Enter);
+    tui.enter("delete");
+    tui.type_text("folder q");
+    tui.press(KeyCode::Tab);
+    tui.type_text("o");
+    tui.press(KeyCode::Tab);
+    assert_snapshot("tab_in_delete_prompt", &tui.frame());
+}
+
+#[test]
+fn edit_keeps_its_task_when_the_list_changes_elsewhere() {
+    let mut tui = Tui::new(80, 24);
+    tui.add("get milk");
+    tui.add("call the plumber");
+    tui.add("water the plants");
+    tui.enter("edit");
+    tui.enter("2");
+    // Another instance deletes the first task; the next key press reloads
+    // the list and merges that in.
+    tui.change_elsewhere(|tasks| {
+        tasks.remove(0);
+    });
+    tui.press_with(KeyCode::Char('w'), KeyModifiers::CONTROL);
+    tui.type_text("electrician");
+    assert_snapshot("edit_after_reload", &tui.frame());
+    tui.press(KeyCode::Enter);
+    assert_snapshot("edit_after_reload_saved", &tui.frame());
+    let stored = tui.storage.store.load().unwrap();
+    assert_eq!(stored, tui.tasks);
+    assert_eq!(stored[0].text, "call the electrician");
+}
+
+#[test]
+fn edit_with_new_text_on_one_line() {
+    let mut tui = Tui::new(80, 24);
+    tui.add("get milk");
+    tui.add("call the plumbr");
+    tui.enter("edit 2 call the plumber");
+    assert_snapshot("edit_inline_text", &tui.frame());
+    tui.press(KeyCode::Enter);
+    assert_snapshot("edit_inline_text_saved", &tui.frame());
+}
+
+#[test]
+fn edit_keeps_both_versions_when_the_task_changes_elsewhere() {
+    let mut tui = Tui::new(80, 24);
+    tui.add("call the plumber");
+    tui.add("water the plants");
+    tui.enter("edit 1 call the electrician");
+    tui.change_elsewhere(|tasks| tasks[0].text = "call the plumber at 9".to_string());
+    tui.press(KeyCode::Enter);
+    assert_snapshot("edit_changed_elsewhere", &tui.frame());
+    let texts: Vec<&str> = tui.tasks.iter().map(|t| t.text.as_str()).collect();
+    assert_eq!(
+        texts,
+        [
+            "call the plumber at 9",
+            "call the electrician",
+            "water the plants"
+        ]
+    );
+
+    tui.enter("edit 3 water the lawn");
+    tui.change_elsewhere(|tasks| {
+        tasks.pop();
+    });
+    tui.press(KeyCode::Enter);
+    assert_eq!(tui.tasks[2].text, "water the lawn");
+}
+
+#[test]
+fn edit_with_only_tokens_keeps_the_text() {
+    let mut tui = Tui::new(80, 24);
+    tui.add("water the plants");
+    tui.enter("edit 1 pri:high due:2026-11-01");
+    tui.press(KeyCode::Enter);
+    let task = &tui.tasks[0];
+    assert_eq!(task.text, "water the plants");
+    assert_eq!(task.priority, Some(Priority::High));
+    assert_eq!(
+        task.due.map(|d| d.to_string()).as_deref(),
+        Some("2026-11-01")
+    );
+}
+
+#[test]
+fn narrow_terminal_drops_date_columns() {
+    let mut tui = Tui::new(40, 24);
+    tui.add("a task with a rather long description that will not fit");
+    assert_snapshot("narrow_terminal", &tui.frame());
+}
+
+#[test]
+fn terminal_too_small() {
+    let tui = Tui::new(30, 10);
+    assert_snapshot("terminal_too_small", &tui.frame());
+}